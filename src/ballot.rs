@@ -1,13 +1,17 @@
-use std::collections::{HashMap, HashSet};
-use std::time::Duration;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime};
 
 use crate::cooldown::Cooldown;
+use crate::nomination::{Match, Nomination};
 
 pub enum VoteError {
     Type,
     Progress,
-    Nomination,
+    Nomination(Vec<String>),
+    Ambiguous(Vec<String>),
     Voters,
+    Target,
+    Recent(usize),
     Cooldown(f32),
 }
 
@@ -27,9 +31,13 @@ pub struct Ballot {
     player_cooldown: HashMap<String, Cooldown>,
     cooldown_duration: Duration,
     target: f32,
-    nominations: HashMap<String, HashSet<String>>,
+    nominations: HashMap<String, Nomination>,
     proposed: String,
+    proposer: String,
     r#type: String,
+    excluded: Option<String>,
+    recent_maps: VecDeque<String>,
+    rotation_cooldown: usize,
 }
 
 impl Ballot {
@@ -37,7 +45,8 @@ impl Ballot {
         voting_duration: Duration,
         cooldown_duration: Duration,
         target: f32,
-        nominations: HashMap<String, HashSet<String>>,
+        nominations: HashMap<String, Nomination>,
+        rotation_cooldown: usize,
     ) -> Ballot {
         Ballot {
             voting: false,
@@ -48,10 +57,14 @@ impl Ballot {
             player_vote: HashMap::new(),
             player_cooldown: HashMap::new(),
             cooldown_duration: cooldown_duration + voting_duration,
-            target: target,
-            nominations: nominations,
+            target,
+            nominations,
             proposed: String::new(),
+            proposer: String::new(),
             r#type: String::new(),
+            excluded: None,
+            recent_maps: VecDeque::new(),
+            rotation_cooldown,
         }
     }
     fn reset(&mut self) {
@@ -60,31 +73,72 @@ impl Ballot {
         self.nays = 0;
         self.voting_duration.clear_cooldown();
         self.player_vote.clear();
+        self.excluded = None;
     }
     pub fn start_voting(
         &mut self,
-        id: &String,
-        r#type: &String,
-        proposal: &String,
+        id: &str,
+        r#type: &str,
+        proposal: &str,
     ) -> Result<(), VoteError> {
+        self.check_proposer(id)?;
+        let proposal = self.check_proposal(r#type, proposal)?;
+        self.open(id, r#type, &proposal);
+        Ok(())
+    }
+    /// Checks that the value can be proposed under the nomination category,
+    /// returning the full value a partial or misspelled one stands for.
+    pub fn check_proposal(&self, r#type: &str, proposal: &str) -> Result<String, VoteError> {
+        match self.nominations.get(r#type) {
+            Some(nomination) => {
+                let proposal = match nomination.find(proposal) {
+                    Match::Found(value) => value,
+                    Match::Ambiguous(candidates) => return Err(VoteError::Ambiguous(candidates)),
+                    Match::Missing(suggestions) => return Err(VoteError::Nomination(suggestions)),
+                };
+                if nomination.is_rotation() {
+                    if let Some(maps_left) = self.get_maps_until_available(&proposal) {
+                        return Err(VoteError::Recent(maps_left));
+                    }
+                }
+                Ok(proposal)
+            }
+            None => Err(VoteError::Type),
+        }
+    }
+    /// Starts a motion against a player, whose `target` voter is left out of the vote
+    /// and must be left out of the voter count.
+    pub fn start_targeted_voting(
+        &mut self,
+        id: &str,
+        r#type: &str,
+        proposal: &str,
+        target: &str,
+    ) -> Result<(), VoteError> {
+        self.check_proposer(id)?;
+        self.open(id, r#type, proposal);
+        self.excluded = Some(target.to_owned());
+        Ok(())
+    }
+    fn check_proposer(&mut self, id: &str) -> Result<(), VoteError> {
         if self.is_user_in_cooldown(id) {
             return Err(VoteError::Cooldown(self.get_user_cooldown(id)));
         }
         if self.voting {
             return Err(VoteError::Progress);
         }
-        if !self.nominations.contains_key(r#type) {
-            return Err(VoteError::Type);
-        }
-        if !self.nominations.get(r#type).unwrap().contains(proposal) {
-            return Err(VoteError::Nomination);
-        }
+        Ok(())
+    }
+    fn open(&mut self, id: &str, r#type: &str, proposal: &str) {
         self.put_user_in_cooldown(id);
         self.voting_duration.put_in_cooldown();
         self.voting = true;
         self.proposed = proposal.to_string();
+        self.proposer = id.to_string();
         self.r#type = r#type.to_string();
-        Ok(())
+    }
+    pub fn is_voting(&self) -> bool {
+        self.voting
     }
     pub fn stop_voting(&mut self) -> Result<(), VoteError> {
         if !self.voting {
@@ -93,7 +147,10 @@ impl Ballot {
         self.reset();
         Ok(())
     }
-    pub fn vote(&mut self, id: &String, vote: bool) -> Result<(), VoteError> {
+    pub fn vote(&mut self, id: &str, vote: bool) -> Result<(), VoteError> {
+        if self.is_excluded(id) {
+            return Err(VoteError::Target);
+        }
         self.unvote(id)?;
         self.player_vote.insert(id.to_owned(), vote);
         match vote {
@@ -102,16 +159,15 @@ impl Ballot {
         }
         Ok(())
     }
-    pub fn unvote(&mut self, id: &String) -> Result<(), VoteError> {
+    pub fn unvote(&mut self, id: &str) -> Result<(), VoteError> {
         if !self.voting {
             return Err(VoteError::Progress);
         }
-        match self.player_vote.remove(id) {
-            Some(value) => match value {
+        if let Some(value) = self.player_vote.remove(id) {
+            match value {
                 true => self.yays -= 1,
                 false => self.nays -= 1,
-            },
-            None => {}
+            }
         }
         Ok(())
     }
@@ -129,15 +185,16 @@ impl Ballot {
         self.set_voters(self.voters - 1)
     }
     pub fn get_requirements(&self) -> (i8, i8) {
-        let voters_yay = (self.voters as f32 * self.target).ceil() as i8;
-        let voters_nay = self.voters - voters_yay;
+        let voters = self.voters;
+        let voters_yay = (voters as f32 * self.target).ceil() as i8;
+        let voters_nay = voters - voters_yay;
         (voters_yay, if voters_nay == 0 { 1 } else { voters_nay })
     }
     pub fn get_result(&self, majority_result: bool) -> Result<VoteResult, VoteError> {
         if !self.voting {
             return Err(VoteError::Progress);
         }
-        if self.voters == 0 {
+        if self.voters <= 0 {
             return Err(VoteError::Voters);
         }
         let (yay, nay) = self.get_requirements();
@@ -173,7 +230,7 @@ impl Ballot {
     pub fn is_voting_finished(&self) -> bool {
         self.voting_duration.is_in_cooldown()
     }
-    pub fn is_user_in_cooldown(&mut self, id: &String) -> bool {
+    pub fn is_user_in_cooldown(&mut self, id: &str) -> bool {
         match self.player_cooldown.get(id) {
             Some(value) => value.is_in_cooldown(),
             None => {
@@ -183,13 +240,13 @@ impl Ballot {
             }
         }
     }
-    pub fn get_user_cooldown(&mut self, id: &String) -> f32 {
+    pub fn get_user_cooldown(&mut self, id: &str) -> f32 {
         match self.player_cooldown.get(id) {
             Some(value) => value.get_remaining_time(),
             None => 0f32,
         }
     }
-    pub fn put_user_in_cooldown(&mut self, id: &String) {
+    pub fn put_user_in_cooldown(&mut self, id: &str) {
         match self.player_cooldown.get_mut(id) {
             Some(value) => {
                 value.put_in_cooldown();
@@ -201,10 +258,67 @@ impl Ballot {
             }
         }
     }
-    pub fn remove_user_cooldown(&mut self, id: &String) {
+    pub fn remove_user_cooldown(&mut self, id: &str) {
         self.player_cooldown.remove(id);
     }
+    /// Returns when each cooldown that is still running ends.
+    pub fn get_cooldowns(&self) -> HashMap<String, SystemTime> {
+        self.player_cooldown
+            .iter()
+            .filter(|(_, cooldown)| cooldown.is_in_cooldown())
+            .map(|(id, cooldown)| (id.to_owned(), cooldown.get_expiry()))
+            .collect()
+    }
+    pub fn set_user_cooldown(&mut self, id: &str, expiry: SystemTime) {
+        let mut cooldown = Cooldown::new(self.cooldown_duration);
+        cooldown.set_expiry(expiry);
+        self.player_cooldown.insert(id.to_owned(), cooldown);
+    }
+    /// Remembers the map that was just loaded, forgetting the oldest one once the
+    /// rotation cooldown is full. Restarts of the same map are only counted once.
+    pub fn add_played_map(&mut self, map: &str) {
+        if self.rotation_cooldown == 0 || self.recent_maps.back().map(String::as_str) == Some(map) {
+            return;
+        }
+        self.recent_maps.push_back(map.to_owned());
+        while self.recent_maps.len() > self.rotation_cooldown {
+            self.recent_maps.pop_front();
+        }
+    }
+    pub fn get_recent_maps(&self) -> Vec<String> {
+        self.recent_maps.iter().cloned().collect()
+    }
+    /// Counts how many other maps need to be played before `map` can be voted again.
+    pub fn get_maps_until_available(&self, map: &str) -> Option<usize> {
+        let position = self.recent_maps.iter().rposition(|recent| recent == map)?;
+        Some(position + 1 + self.rotation_cooldown - self.recent_maps.len())
+    }
+    pub fn get_proposal(&self) -> &str {
+        &self.proposed
+    }
+    pub fn get_proposer(&self) -> &str {
+        &self.proposer
+    }
+    pub fn get_player_votes(&self) -> &HashMap<String, bool> {
+        &self.player_vote
+    }
+    pub fn get_start_time(&self) -> SystemTime {
+        self.voting_duration.get_start_time()
+    }
     pub fn get_type(&self) -> String {
         self.r#type.clone()
     }
+    pub fn get_excluded(&self) -> Option<&str> {
+        self.excluded.as_deref()
+    }
+    pub fn is_excluded(&self, id: &str) -> bool {
+        self.excluded.as_deref() == Some(id)
+    }
+    /// Lets the target's voter take part again, once it no longer stands for them.
+    pub fn clear_excluded(&mut self) {
+        self.excluded = None;
+    }
+    pub fn get_nomination(&self, r#type: &str) -> Option<&Nomination> {
+        self.nominations.get(r#type)
+    }
 }
@@ -0,0 +1,14 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// How far a replay has moved ahead of the wall clock, in milliseconds.
+static OFFSET: AtomicU64 = AtomicU64::new(0);
+
+/// The current time, which a replay can move forward faster than real time.
+pub fn now() -> SystemTime {
+    SystemTime::now() + Duration::from_millis(OFFSET.load(Ordering::Relaxed))
+}
+
+pub fn advance(duration: Duration) {
+    OFFSET.fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+}
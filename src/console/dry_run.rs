@@ -0,0 +1,39 @@
+use super::{CommandSink, RconError};
+
+/// Prints the commands instead of sending them, and keeps them for later. The
+/// commands are preceded by the server name when there is one.
+pub struct DryRun {
+    prefix: String,
+    commands: Vec<String>,
+}
+
+impl DryRun {
+    pub fn new(server: &str) -> Self {
+        let prefix = match server.is_empty() {
+            true => String::new(),
+            false => format!("[{}] ", server),
+        };
+        Self {
+            prefix,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn get_commands(&self) -> &[String] {
+        &self.commands
+    }
+}
+
+impl CommandSink for DryRun {
+    fn rcon_send(&mut self, command: &[u8]) -> Result<String, RconError> {
+        let command = String::from_utf8_lossy(command).into_owned();
+        println!("{}rcon {}", self.prefix, command);
+        self.commands.push(command);
+        Ok(String::new())
+    }
+
+    fn query(&mut self, request: &[u8]) -> Result<String, RconError> {
+        println!("{}{}", self.prefix, String::from_utf8_lossy(request));
+        Ok(String::new())
+    }
+}
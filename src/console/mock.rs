@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use super::{CommandSink, RconError};
+
+/// Keeps the commands instead of sending them and answers with the responses
+/// set up beforehand, for tests.
+#[derive(Default)]
+pub struct MockConsole {
+    commands: Vec<String>,
+    responses: HashMap<String, String>,
+}
+
+impl MockConsole {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers `command` with `response` from now on, other commands get an empty answer.
+    pub fn respond(&mut self, command: &str, response: &str) {
+        self.responses
+            .insert(command.to_owned(), response.to_owned());
+    }
+
+    pub fn get_commands(&self) -> &[String] {
+        &self.commands
+    }
+
+    /// Hands over the commands received since the last call.
+    pub fn take_commands(&mut self) -> Vec<String> {
+        self.commands.drain(..).collect()
+    }
+}
+
+impl CommandSink for MockConsole {
+    fn rcon_send(&mut self, command: &[u8]) -> Result<String, RconError> {
+        let command = String::from_utf8_lossy(command).into_owned();
+        let response = self.responses.get(&command).cloned().unwrap_or_default();
+        self.commands.push(command);
+        Ok(response)
+    }
+
+    fn query(&mut self, request: &[u8]) -> Result<String, RconError> {
+        self.rcon_send(request)
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+mod dry_run;
+#[cfg(test)]
+mod mock;
+mod query;
+mod queue;
+mod rcon;
+
+pub use dry_run::DryRun;
+#[cfg(test)]
+pub use mock::MockConsole;
+pub use query::{ServerStatus, Status};
+pub use queue::Queue;
+pub use rcon::RconConsole;
+
+pub enum RconError {
+    BadPassword,
+    NoResponse,
+    Io(io::Error),
+    Closed,
+}
+
+impl fmt::Display for RconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RconError::BadPassword => write!(f, "the rcon password was refused"),
+            RconError::NoResponse => write!(f, "the server did not answer"),
+            RconError::Io(error) => write!(f, "{}", error),
+            RconError::Closed => write!(f, "the command queue is closed"),
+        }
+    }
+}
+
+/// Where server commands go: the game server itself, or somewhere they can be
+/// looked at without touching the server.
+pub trait CommandSink {
+    /// Runs a command, returning what the server printed.
+    fn rcon_send(&mut self, command: &[u8]) -> Result<String, RconError>;
+
+    /// Runs a command whose answer is not needed, which may happen later.
+    fn rcon_post(&mut self, command: &[u8]) -> Result<(), RconError> {
+        self.rcon_send(command).map(|_| ())
+    }
+
+    /// Sends a request that needs no rcon password, like `getstatus`.
+    fn query(&mut self, request: &[u8]) -> Result<String, RconError>;
+
+    fn svsay(&mut self, payload: &[u8]) -> Result<(), RconError> {
+        let command = [b"svsay ", payload].concat();
+        self.rcon_post(&command)
+    }
+
+    fn svtell(&mut self, id: &[u8], payload: &[u8]) -> Result<(), RconError> {
+        let command = [b"svtell ", id, b" ", payload].concat();
+        self.rcon_post(&command)
+    }
+
+    fn map(&mut self, map: &[u8]) -> Result<(), RconError> {
+        let command = [b"map ", map].concat();
+        self.rcon_post(&command)
+    }
+
+    fn mbmode(&mut self, mode: &[u8]) -> Result<(), RconError> {
+        let command = [b"mbmode ", mode].concat();
+        self.rcon_post(&command)
+    }
+
+    fn status(&mut self) -> Result<Status, RconError> {
+        self.rcon_send(b"status")
+            .map(|response| query::parse_status(&response))
+    }
+
+    fn cvar_get(&mut self, name: &str) -> Result<Option<String>, RconError> {
+        self.rcon_send(name.as_bytes())
+            .map(|response| query::parse_cvar(&response))
+    }
+
+    fn cvar_set(&mut self, name: &str, value: &str) -> Result<String, RconError> {
+        let command = format!("set {} \"{}\"", name, value);
+        self.rcon_send(command.as_bytes())
+    }
+
+    fn dumpuser(&mut self, id: &str) -> Result<HashMap<String, String>, RconError> {
+        let command = format!("dumpuser {}", id);
+        self.rcon_send(command.as_bytes())
+            .map(|response| query::parse_dumpuser(&response))
+    }
+
+    fn getstatus(&mut self) -> Result<ServerStatus, RconError> {
+        self.query(b"getstatus")
+            .map(|response| query::parse_server_status(&response))
+    }
+}
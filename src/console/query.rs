@@ -0,0 +1,324 @@
+use lazy_static::lazy_static;
+
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::util::get_userinfo;
+
+lazy_static! {
+    static ref REGEX_STATUS_MAP: Regex = Regex::new(r#"^map *: *(?P<map>\S+)"#).unwrap();
+    static ref REGEX_STATUS_HEADER_LASTMSG: Regex =
+        Regex::new(r#"^ *(?:num|cl) +score +ping +name +lastmsg\b"#).unwrap();
+    static ref REGEX_STATUS_PLAYER: Regex = Regex::new(
+        r#"^ *(?P<slot>[0-9]+) +(?P<score>-?[0-9]+) +(?P<ping>[0-9]+|CNCT|ZMBI) +(?P<name>.*?) +(?P<address>[0-9.]+:[0-9]+|bot|loopback|localhost)\b"#
+    )
+    .unwrap();
+    static ref REGEX_STATUS_PLAYER_LASTMSG: Regex = Regex::new(
+        r#"^ *(?P<slot>[0-9]+) +(?P<score>-?[0-9]+) +(?P<ping>[0-9]+|CNCT|ZMBI) +(?P<name>.*?) +[0-9]+ +(?P<address>[0-9.]+:[0-9]+|bot|loopback|localhost)\b"#
+    )
+    .unwrap();
+    static ref REGEX_CVAR: Regex = Regex::new(r#""(?P<name>[^"]+)" is:"(?P<value>[^"]*?)(?:\^7)?""#).unwrap();
+    static ref REGEX_STATUS_RESPONSE_PLAYER: Regex =
+        Regex::new(r#"^(?P<score>-?[0-9]+) (?P<ping>[0-9]+)(?: [0-9-]+)* "(?P<name>.*)""#).unwrap();
+}
+
+const STATUS_RESPONSE_HEADER: &str = "statusResponse";
+
+/// A row of the `status` table.
+pub struct StatusPlayer {
+    pub slot: String,
+    pub score: i32,
+    /// `None` while the client is still connecting.
+    pub ping: Option<u16>,
+    pub name: String,
+    pub address: String,
+}
+
+impl StatusPlayer {
+    /// The IP address without the port, `None` for bots and local clients.
+    pub fn get_ip(&self) -> Option<String> {
+        let (ip, _) = self.address.rsplit_once(':')?;
+        Some(ip.to_owned())
+    }
+    pub fn is_bot(&self) -> bool {
+        self.address == "bot"
+    }
+}
+
+pub struct Status {
+    pub map: Option<String>,
+    pub players: Vec<StatusPlayer>,
+}
+
+/// A player as listed by `getstatus`, which has no slots or addresses.
+pub struct StatusResponsePlayer {
+    pub score: i32,
+    pub ping: u16,
+    pub name: String,
+}
+
+pub struct ServerStatus {
+    pub info: HashMap<String, String>,
+    pub players: Vec<StatusResponsePlayer>,
+}
+
+/// Reads the map and the player table, which has a `lastmsg` column before the
+/// address on some servers.
+pub fn parse_status(response: &str) -> Status {
+    let mut status = Status {
+        map: None,
+        players: Vec::new(),
+    };
+    let player_regex = match response
+        .lines()
+        .any(|line| REGEX_STATUS_HEADER_LASTMSG.is_match(line))
+    {
+        true => &*REGEX_STATUS_PLAYER_LASTMSG,
+        false => &*REGEX_STATUS_PLAYER,
+    };
+    for line in response.lines() {
+        if let Some(captures) = REGEX_STATUS_MAP.captures(line) {
+            status.map = Some(
+                captures
+                    .name("map")
+                    .expect("could not get status 'map'")
+                    .as_str()
+                    .to_owned(),
+            );
+        } else if let Some(captures) = player_regex.captures(line) {
+            status.players.push(StatusPlayer {
+                slot: captures
+                    .name("slot")
+                    .expect("could not get status 'slot'")
+                    .as_str()
+                    .to_owned(),
+                score: captures
+                    .name("score")
+                    .expect("could not get status 'score'")
+                    .as_str()
+                    .parse()
+                    .unwrap_or(0),
+                ping: captures
+                    .name("ping")
+                    .expect("could not get status 'ping'")
+                    .as_str()
+                    .parse()
+                    .ok(),
+                name: captures
+                    .name("name")
+                    .expect("could not get status 'name'")
+                    .as_str()
+                    .to_owned(),
+                address: captures
+                    .name("address")
+                    .expect("could not get status 'address'")
+                    .as_str()
+                    .to_owned(),
+            });
+        }
+    }
+    status
+}
+
+/// Reads `"g_gametype" is:"7^7" default:"0^7"`, `None` for unknown cvars.
+pub fn parse_cvar(response: &str) -> Option<String> {
+    let captures = REGEX_CVAR.captures(response)?;
+    Some(
+        captures
+            .name("value")
+            .expect("could not get cvar 'value'")
+            .as_str()
+            .to_owned(),
+    )
+}
+
+/// Reads the key and value columns that follow the `--------` line.
+pub fn parse_dumpuser(response: &str) -> HashMap<String, String> {
+    response
+        .lines()
+        .skip_while(|line| !line.starts_with("---"))
+        .skip(1)
+        .filter_map(|line| {
+            let line = line.trim();
+            let split = line.find(char::is_whitespace)?;
+            let (key, value) = line.split_at(split);
+            Some((key.to_owned(), value.trim().to_owned()))
+        })
+        .collect()
+}
+
+/// Reads the serverinfo line and the player lines of a `statusResponse`.
+pub fn parse_server_status(response: &str) -> ServerStatus {
+    let mut lines = response
+        .lines()
+        .skip_while(|line| line.trim() == STATUS_RESPONSE_HEADER);
+    let info = lines.next().map(get_userinfo).unwrap_or_default();
+    let players = lines
+        .filter_map(|line| REGEX_STATUS_RESPONSE_PLAYER.captures(line))
+        .map(|captures| StatusResponsePlayer {
+            score: captures
+                .name("score")
+                .expect("could not get getstatus 'score'")
+                .as_str()
+                .parse()
+                .unwrap_or(0),
+            ping: captures
+                .name("ping")
+                .expect("could not get getstatus 'ping'")
+                .as_str()
+                .parse()
+                .unwrap_or(0),
+            name: captures
+                .name("name")
+                .expect("could not get getstatus 'name'")
+                .as_str()
+                .to_owned(),
+        })
+        .collect();
+    ServerStatus { info, players }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{CommandSink, MockConsole};
+    use super::*;
+
+    const STATUS: &str = "hostname: Movie Battles\n\
+        map     : mb2_dotf\n\
+        \n\
+        cl score ping name            address                                 rate \n\
+        -- ----- ---- --------------- --------------------------------------- -----\n\
+        \x20 0     5   36 ^1Alice^7      1.2.3.4:29071                           25000\n\
+        \x20 1    -2   40 Bob the Great   5.6.7.8:29070                           25000\n\
+        \x20 2     0    0 Bot             bot                                     25000\n\
+        \x2012     0 CNCT Joining         9.9.9.9:29070                           25000\n";
+
+    #[test]
+    fn status_map() {
+        assert_eq!(parse_status(STATUS).map, Some("mb2_dotf".to_owned()));
+        assert_eq!(parse_status("").map, None);
+    }
+
+    #[test]
+    fn status_players() {
+        let players = parse_status(STATUS).players;
+        assert_eq!(players.len(), 4);
+        assert_eq!(players[0].slot, "0");
+        assert_eq!(players[0].score, 5);
+        assert_eq!(players[0].ping, Some(36));
+        assert_eq!(players[0].name, "^1Alice^7");
+        assert_eq!(players[0].get_ip(), Some("1.2.3.4".to_owned()));
+        assert_eq!(players[1].name, "Bob the Great");
+        assert_eq!(players[1].score, -2);
+        assert!(players[2].is_bot());
+        assert_eq!(players[2].get_ip(), None);
+        assert_eq!(players[3].slot, "12");
+        assert_eq!(players[3].ping, None);
+    }
+
+    #[test]
+    fn status_names_ending_in_a_number() {
+        let response = "map: mb2_kamino\n\
+            cl score ping name            address                                 rate\n\
+            \x20 4     7   48 Player 77       10.0.0.6:29070                          25000\n";
+        assert_eq!(parse_status(response).players[0].name, "Player 77");
+        let response = "map: mb2_kamino\n\
+            num score ping name            lastmsg address               qport rate\n\
+            \x20 4     7   48 Player 77            0 10.0.0.6:29070        1234 25000\n";
+        let players = parse_status(response).players;
+        assert_eq!(players[0].name, "Player 77");
+        assert_eq!(players[0].get_ip(), Some("10.0.0.6".to_owned()));
+    }
+
+    #[test]
+    fn status_with_lastmsg_column() {
+        let response = "map: mb2_kamino\n\
+            num score ping name            lastmsg address               qport rate\n\
+            \x20 3     7   48 Padawan              0 10.0.0.5:29070        1234 25000\n";
+        let status = parse_status(response);
+        assert_eq!(status.map, Some("mb2_kamino".to_owned()));
+        assert_eq!(status.players.len(), 1);
+        assert_eq!(status.players[0].name, "Padawan");
+        assert_eq!(status.players[0].get_ip(), Some("10.0.0.5".to_owned()));
+    }
+
+    #[test]
+    fn cvar() {
+        assert_eq!(
+            parse_cvar("\"g_gametype\" is:\"7^7\" default:\"0^7\"\n"),
+            Some("7".to_owned())
+        );
+        assert_eq!(
+            parse_cvar("\"sv_hostname\" is:\"^1Movie ^7Battles^7\" default:\"noname^7\"\n"),
+            Some("^1Movie ^7Battles".to_owned())
+        );
+        assert_eq!(
+            parse_cvar("\"g_motd\" is:\"^7\" default:\"^7\"\n"),
+            Some(String::new())
+        );
+        assert_eq!(parse_cvar("Unknown command \"g_nothing\"\n"), None);
+    }
+
+    #[test]
+    fn dumpuser() {
+        let response = "userinfo\n\
+            --------\n\
+            ip                  1.2.3.4:29071\n\
+            name                Bob the Great\n\
+            ja_guid             ABCDEF0123456789\n";
+        let userinfo = parse_dumpuser(response);
+        assert_eq!(userinfo.len(), 3);
+        assert_eq!(userinfo["ip"], "1.2.3.4:29071");
+        assert_eq!(userinfo["name"], "Bob the Great");
+        assert_eq!(userinfo["ja_guid"], "ABCDEF0123456789");
+        assert!(parse_dumpuser("Bad slot number: 40\n").is_empty());
+    }
+
+    #[test]
+    fn server_status() {
+        let response = "statusResponse\n\
+            \\mapname\\mb2_dotf\\sv_maxclients\\32\\g_gametype\\7\n\
+            5 36 \"^1Alice^7\"\n\
+            -2 0 \"Bob the Great\"\n";
+        let status = parse_server_status(response);
+        assert_eq!(status.info["mapname"], "mb2_dotf");
+        assert_eq!(status.info["sv_maxclients"], "32");
+        assert_eq!(status.players.len(), 2);
+        assert_eq!(status.players[0].score, 5);
+        assert_eq!(status.players[0].ping, 36);
+        assert_eq!(status.players[0].name, "^1Alice^7");
+        assert_eq!(status.players[1].score, -2);
+        assert_eq!(status.players[1].name, "Bob the Great");
+        assert!(parse_server_status("statusResponse\n").players.is_empty());
+    }
+
+    #[test]
+    fn queries_send_their_commands() {
+        let mut console = MockConsole::new();
+        console.respond("g_gametype", "\"g_gametype\" is:\"7^7\" default:\"0^7\"\n");
+        console.respond(
+            "dumpuser 3",
+            "userinfo\n--------\nname                Carol\n",
+        );
+        console.respond("getstatus", "statusResponse\n\\mapname\\mb2_dotf\n");
+        assert_eq!(
+            console.cvar_get("g_gametype").ok(),
+            Some(Some("7".to_owned()))
+        );
+        assert!(console.cvar_set("g_gametype", "8").is_ok());
+        assert_eq!(console.dumpuser("3").ok().unwrap()["name"], "Carol");
+        assert_eq!(
+            console.getstatus().ok().unwrap().info["mapname"],
+            "mb2_dotf"
+        );
+        assert_eq!(
+            console.get_commands(),
+            [
+                "g_gametype",
+                "set g_gametype \"8\"",
+                "dumpuser 3",
+                "getstatus"
+            ]
+        );
+    }
+}
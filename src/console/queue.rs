@@ -0,0 +1,299 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+use super::{CommandSink, RconError};
+
+const SAY_PREFIX: &[u8] = b"svsay ";
+const SAY_SEPARATOR: &[u8] = b" | ";
+const DEFAULT_COLOR: &[u8] = b"^7";
+/// The longest message the server broadcasts without cutting it.
+const MAX_SAY_LENGTH: usize = 150;
+
+struct Job {
+    command: Vec<u8>,
+    query: bool,
+    reply: Option<Sender<Result<String, RconError>>>,
+}
+
+/// Hands the commands to another sink on its own thread, so the log is never
+/// held up by the network. Commands are sent no faster than the rate limit,
+/// messages waiting in line are said together and unanswered commands that are
+/// safe to repeat are sent again.
+pub struct Queue {
+    sender: Sender<Job>,
+}
+
+impl Queue {
+    pub fn new<C: CommandSink + Send + 'static>(
+        sink: C,
+        packets_per_second: f32,
+        retries: usize,
+    ) -> Queue {
+        let (sender, receiver) = channel();
+        let interval = match packets_per_second > 0f32 {
+            true => Duration::from_secs_f32(1f32 / packets_per_second),
+            false => Duration::from_secs(0),
+        };
+        thread::spawn(move || run(sink, receiver, interval, retries));
+        Queue { sender }
+    }
+
+    fn push(&self, command: &[u8], query: bool) -> Result<String, RconError> {
+        let (reply, answer) = channel();
+        let job = Job {
+            command: command.to_vec(),
+            query,
+            reply: Some(reply),
+        };
+        self.sender.send(job).map_err(|_| RconError::Closed)?;
+        answer.recv().map_err(|_| RconError::Closed)?
+    }
+}
+
+impl CommandSink for Queue {
+    /// Waits for the commands queued before it and for the answer.
+    fn rcon_send(&mut self, command: &[u8]) -> Result<String, RconError> {
+        self.push(command, false)
+    }
+
+    fn rcon_post(&mut self, command: &[u8]) -> Result<(), RconError> {
+        let job = Job {
+            command: command.to_vec(),
+            query: false,
+            reply: None,
+        };
+        self.sender.send(job).map_err(|_| RconError::Closed)
+    }
+
+    fn query(&mut self, request: &[u8]) -> Result<String, RconError> {
+        self.push(request, true)
+    }
+}
+
+fn run<C: CommandSink>(mut sink: C, receiver: Receiver<Job>, interval: Duration, retries: usize) {
+    let mut backlog: VecDeque<Job> = VecDeque::new();
+    let mut last_sent: Option<Instant> = None;
+    loop {
+        let job = match backlog.pop_front() {
+            Some(job) => job,
+            None => match receiver.recv() {
+                Ok(job) => job,
+                Err(_) => return,
+            },
+        };
+        wait(interval, last_sent);
+        backlog.extend(receiver.try_iter());
+        let job = coalesce(job, &mut backlog);
+        let mut result = execute(&mut sink, &job);
+        last_sent = Some(Instant::now());
+        if is_repeatable(&job) {
+            for _ in 0..retries {
+                match result {
+                    Err(RconError::NoResponse) => {
+                        // A retry counts against the rate limit like any other command.
+                        wait(interval, last_sent);
+                        result = execute(&mut sink, &job);
+                        last_sent = Some(Instant::now());
+                    }
+                    _ => break,
+                }
+            }
+        }
+        match job.reply {
+            Some(reply) => {
+                reply.send(result).ok();
+            }
+            None => {
+                if let Err(error) = result {
+                    eprintln!(
+                        "could not run '{}': {}",
+                        String::from_utf8_lossy(&job.command),
+                        error
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Sleeps until `interval` has passed since the last command was sent.
+fn wait(interval: Duration, last_sent: Option<Instant>) {
+    if let Some(last_sent) = last_sent {
+        if let Some(wait) = interval.checked_sub(last_sent.elapsed()) {
+            sleep(wait);
+        }
+    }
+}
+
+fn execute<C: CommandSink>(sink: &mut C, job: &Job) -> Result<String, RconError> {
+    match job.query {
+        true => sink.query(&job.command),
+        false => sink.rcon_send(&job.command),
+    }
+}
+
+/// Joins the messages waiting in line into one `svsay` while they fit.
+fn coalesce(mut job: Job, backlog: &mut VecDeque<Job>) -> Job {
+    if !is_say(&job) {
+        return job;
+    }
+    let mut colored = job.command.contains(&b'^');
+    while let Some(next) = backlog.front() {
+        if !is_say(next) {
+            break;
+        }
+        let message = &next.command[SAY_PREFIX.len()..];
+        let length = job.command.len() - SAY_PREFIX.len()
+            + DEFAULT_COLOR.len()
+            + SAY_SEPARATOR.len()
+            + message.len();
+        if length > MAX_SAY_LENGTH {
+            break;
+        }
+        // The color a message ends with would carry over to the next one.
+        if colored {
+            job.command.extend_from_slice(DEFAULT_COLOR);
+        }
+        colored = message.contains(&b'^');
+        job.command.extend_from_slice(SAY_SEPARATOR);
+        job.command.extend_from_slice(message);
+        backlog.pop_front();
+    }
+    job
+}
+
+fn is_say(job: &Job) -> bool {
+    job.reply.is_none() && !job.query && job.command.starts_with(SAY_PREFIX)
+}
+
+/// A command the server may have run without its answer getting back, like
+/// `map` or `addip`, must not be sent twice. Queries, the commands waiting for
+/// their answer (status, cvars) and chat messages can be.
+fn is_repeatable(job: &Job) -> bool {
+    job.query || job.reply.is_some() || is_say(job)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    fn post(command: &str) -> Job {
+        Job {
+            command: command.as_bytes().to_vec(),
+            query: false,
+            reply: None,
+        }
+    }
+
+    fn backlog(commands: &[&str]) -> VecDeque<Job> {
+        commands.iter().map(|command| post(command)).collect()
+    }
+
+    fn text(job: &Job) -> String {
+        String::from_utf8_lossy(&job.command).into_owned()
+    }
+
+    #[test]
+    fn only_harmless_commands_are_repeated() {
+        let (reply, _answer) = channel();
+        let status = Job {
+            command: b"status".to_vec(),
+            query: false,
+            reply: Some(reply),
+        };
+        assert!(is_repeatable(&status));
+        assert!(is_repeatable(&post("svsay hello")));
+        assert!(!is_repeatable(&post("map mb2_dotf")));
+        assert!(!is_repeatable(&post("addip 10.0.0.4")));
+    }
+
+    /// Never answers, and notes when each command came in.
+    struct Silent {
+        sent: Arc<Mutex<Vec<Instant>>>,
+    }
+
+    impl CommandSink for Silent {
+        fn rcon_send(&mut self, _command: &[u8]) -> Result<String, RconError> {
+            self.sent
+                .lock()
+                .expect("could not lock sent times")
+                .push(Instant::now());
+            Err(RconError::NoResponse)
+        }
+
+        fn query(&mut self, request: &[u8]) -> Result<String, RconError> {
+            self.rcon_send(request)
+        }
+    }
+
+    #[test]
+    fn retries_keep_to_the_rate() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sink = Silent {
+            sent: Arc::clone(&sent),
+        };
+        let mut queue = Queue::new(sink, 10f32, 2);
+        assert!(queue.rcon_send(b"status").is_err());
+        let sent = sent.lock().expect("could not lock sent times");
+        assert_eq!(sent.len(), 3);
+        for pair in sent.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn messages_are_joined() {
+        let mut backlog = backlog(&["svsay two", "svsay three"]);
+        let job = coalesce(post("svsay one"), &mut backlog);
+        assert_eq!(text(&job), "svsay one | two | three");
+        assert!(backlog.is_empty());
+    }
+
+    #[test]
+    fn color_is_reset_between_messages() {
+        let mut backlog = backlog(&["svsay ^1red", "svsay plain", "svsay last"]);
+        let job = coalesce(post("svsay ^2green"), &mut backlog);
+        assert_eq!(text(&job), "svsay ^2green^7 | ^1red^7 | plain | last");
+    }
+
+    #[test]
+    fn other_commands_are_kept_in_order() {
+        let mut backlog = backlog(&["map mb2_dotf", "svsay two"]);
+        let job = coalesce(post("svsay one"), &mut backlog);
+        assert_eq!(text(&job), "svsay one");
+        assert_eq!(backlog.len(), 2);
+        let job = coalesce(post("clientkick 3"), &mut backlog);
+        assert_eq!(text(&job), "clientkick 3");
+        assert_eq!(backlog.len(), 2);
+    }
+
+    #[test]
+    fn answered_commands_are_not_joined() {
+        let (reply, _answer) = channel();
+        let mut backlog = VecDeque::new();
+        backlog.push_back(Job {
+            command: b"svsay two".to_vec(),
+            query: false,
+            reply: Some(reply),
+        });
+        let job = coalesce(post("svsay one"), &mut backlog);
+        assert_eq!(text(&job), "svsay one");
+        assert_eq!(backlog.len(), 1);
+    }
+
+    #[test]
+    fn long_lines_are_not_joined() {
+        let long = format!("svsay {}", "x".repeat(MAX_SAY_LENGTH - 8));
+        let mut backlog = backlog(&["svsay two", &long, "svsay three"]);
+        let job = coalesce(post("svsay one"), &mut backlog);
+        assert_eq!(text(&job), "svsay one | two");
+        assert_eq!(backlog.len(), 2);
+        let job = coalesce(backlog.pop_front().unwrap(), &mut backlog);
+        assert_eq!(text(&job), long);
+        assert_eq!(backlog.len(), 1);
+    }
+}
@@ -0,0 +1,220 @@
+use std::io::{Error, ErrorKind};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use super::{CommandSink, RconError};
+
+const PAYLOAD_HEADER: &[u8] = b"\xff\xff\xff\xff";
+const PRINT_HEADER: &[u8] = b"print\n";
+const BAD_PASSWORD: &str = "Bad rconpassword.";
+const SELF_CHECK: &[u8] = b"echo democracy";
+
+const ANY_IPV4: &str = "0.0.0.0:0";
+const ANY_IPV6: &str = "[::]:0";
+
+/// Sends the commands to the game server over rcon. The socket is connected to
+/// the server, so packets from anywhere else are ignored.
+pub struct RconConsole {
+    socket: UdpSocket,
+    rcon_password: String,
+}
+
+impl RconConsole {
+    /// Binds to `bind`, or to any port of the server's address family when it is
+    /// not given. The host may be a name, an IPv4 or an IPv6 address.
+    pub fn new(
+        rcon_password: String,
+        host_address: &str,
+        host_port: u16,
+        bind: Option<&str>,
+        read_timeout_duration: Duration,
+    ) -> Result<Self, RconError> {
+        let hosts: Vec<SocketAddr> = (host_address, host_port)
+            .to_socket_addrs()
+            .map_err(RconError::Io)?
+            .collect();
+        let local: Option<SocketAddr> = match bind {
+            Some(bind) => Some(bind.parse().map_err(|_| {
+                RconError::Io(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'{}' is not a socket address", bind),
+                ))
+            })?),
+            None => None,
+        };
+        // The server must be reached over the same IP version as the local address,
+        // IPv4 is preferred when there is none since most servers only listen on it.
+        let host = hosts
+            .iter()
+            .find(|host| match local {
+                Some(local) => local.is_ipv4() == host.is_ipv4(),
+                None => host.is_ipv4(),
+            })
+            .or_else(|| match local {
+                Some(_) => None,
+                None => hosts.first(),
+            })
+            .ok_or_else(|| {
+                RconError::Io(Error::new(
+                    ErrorKind::AddrNotAvailable,
+                    format!(
+                        "{} has no address to reach from the bind address",
+                        host_address
+                    ),
+                ))
+            })?;
+        let socket = match local {
+            Some(local) => UdpSocket::bind(local),
+            None if host.is_ipv4() => UdpSocket::bind(ANY_IPV4),
+            None => UdpSocket::bind(ANY_IPV6),
+        }
+        .map_err(RconError::Io)?;
+        socket.connect(host).map_err(RconError::Io)?;
+        socket
+            .set_read_timeout(Some(read_timeout_duration))
+            .map_err(RconError::Io)?;
+        Ok(Self {
+            socket,
+            rcon_password,
+        })
+    }
+
+    /// Makes sure the server answers and accepts the rcon password.
+    pub fn check(&mut self) -> Result<(), RconError> {
+        self.rcon_send(SELF_CHECK).map(|_| ())
+    }
+
+    pub fn send(&mut self, payload: &[u8]) -> Result<Receiver<'_>, Error> {
+        self.discard_late_packets()?;
+        let payload = [PAYLOAD_HEADER, payload].concat();
+        match self.socket.send(&payload) {
+            Ok(_) => Ok(self.receive()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn receive(&mut self) -> Receiver<'_> {
+        Receiver {
+            socket: &mut self.socket,
+            buffer: [0; 4096],
+        }
+    }
+
+    /// Drops what is left of answers that came after their timeout, so they are
+    /// not taken for the answer to the next command.
+    fn discard_late_packets(&mut self) -> Result<(), Error> {
+        self.socket.set_nonblocking(true)?;
+        while self.socket.recv(&mut [0; 4096]).is_ok() {}
+        self.socket.set_nonblocking(false)
+    }
+}
+
+impl CommandSink for RconConsole {
+    fn rcon_send(&mut self, command: &[u8]) -> Result<String, RconError> {
+        let payload = [b"rcon ", self.rcon_password.as_bytes(), b" ", command].concat();
+        match self.query(&payload) {
+            Ok(ref response) if response.trim_end() == BAD_PASSWORD => Err(RconError::BadPassword),
+            result => result,
+        }
+    }
+
+    /// Long answers come in several packets, so they are read until the timeout.
+    fn query(&mut self, request: &[u8]) -> Result<String, RconError> {
+        let mut response: Option<String> = None;
+        for packet in self.send(request).map_err(RconError::Io)? {
+            response.get_or_insert_with(String::new).push_str(&packet);
+        }
+        response.ok_or(RconError::NoResponse)
+    }
+}
+
+/// Yields the text of each packet the server sends back, without its headers.
+pub struct Receiver<'a> {
+    socket: &'a mut UdpSocket,
+    buffer: [u8; 4096],
+}
+
+impl<'a> Iterator for Receiver<'a> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> {
+        match self.socket.recv(&mut self.buffer) {
+            Ok(byte_count) => {
+                let mut packet = &self.buffer[..byte_count];
+                if packet.starts_with(PAYLOAD_HEADER) {
+                    packet = &packet[PAYLOAD_HEADER.len()..];
+                }
+                if packet.starts_with(PRINT_HEADER) {
+                    packet = &packet[PRINT_HEADER.len()..];
+                }
+                Some(String::from_utf8_lossy(packet).to_string())
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::{self, JoinHandle};
+
+    /// Answers the first packet it gets with `replies`, handing back that packet.
+    fn serve(replies: &'static [&'static [u8]]) -> (RconConsole, JoinHandle<Vec<u8>>) {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let console = RconConsole::new(
+            "secret".to_owned(),
+            "127.0.0.1",
+            port,
+            None,
+            Duration::from_millis(200),
+        )
+        .ok()
+        .unwrap();
+        let handle = thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            let (byte_count, client) = server.recv_from(&mut buffer).unwrap();
+            for reply in replies {
+                server.send_to(reply, client).unwrap();
+            }
+            buffer[..byte_count].to_vec()
+        });
+        (console, handle)
+    }
+
+    #[test]
+    fn bad_password() {
+        let (mut console, handle) = serve(&[b"\xff\xff\xff\xffprint\nBad rconpassword.\n"]);
+        assert!(matches!(
+            console.rcon_send(b"status"),
+            Err(RconError::BadPassword)
+        ));
+        assert_eq!(
+            handle.join().unwrap(),
+            b"\xff\xff\xff\xffrcon secret status"
+        );
+    }
+
+    #[test]
+    fn packets_are_joined_without_headers() {
+        let (mut console, handle) = serve(&[
+            b"\xff\xff\xff\xffprint\nmap: mb2_dotf\n",
+            b"\xff\xff\xff\xffprint\nnum score ping name\n",
+        ]);
+        assert_eq!(
+            console.rcon_send(b"status").ok(),
+            Some("map: mb2_dotf\nnum score ping name\n".to_owned())
+        );
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn no_response() {
+        let (mut console, handle) = serve(&[]);
+        assert!(matches!(
+            console.rcon_send(b"status"),
+            Err(RconError::NoResponse)
+        ));
+        handle.join().unwrap();
+    }
+}
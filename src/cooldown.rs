@@ -1,5 +1,7 @@
 use std::time::{Duration, SystemTime};
 
+use crate::clock;
+
 pub struct Cooldown {
     duration: Duration,
     cooldown: SystemTime,
@@ -8,7 +10,7 @@ pub struct Cooldown {
 impl Cooldown {
     pub fn new(duration: Duration) -> Cooldown {
         Cooldown {
-            duration: duration,
+            duration,
             cooldown: SystemTime::UNIX_EPOCH,
         }
     }
@@ -16,15 +18,29 @@ impl Cooldown {
         self.get_remaining_time() > 0f32
     }
     pub fn get_remaining_time(&self) -> f32 {
-        match self.duration.checked_sub(SystemTime::now().duration_since(self.cooldown).unwrap()) {
+        match self.duration.checked_sub(clock::now().duration_since(self.cooldown).unwrap()) {
             Some(duration) => duration.as_secs_f32(),
             None => 0f32
         }
     }
     pub fn put_in_cooldown(&mut self) {
-        self.cooldown = SystemTime::now();
+        self.cooldown = clock::now();
     }
     pub fn clear_cooldown(&mut self) {
         self.cooldown = SystemTime::UNIX_EPOCH;
     }
+    pub fn get_start_time(&self) -> SystemTime {
+        self.cooldown
+    }
+    pub fn get_expiry(&self) -> SystemTime {
+        self.cooldown + self.duration
+    }
+    /// Puts the cooldown back in place so that it ends at `expiry`, but no later
+    /// than a full cooldown from now.
+    pub fn set_expiry(&mut self, expiry: SystemTime) {
+        self.cooldown = expiry
+            .checked_sub(self.duration)
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+            .min(clock::now());
+    }
 }
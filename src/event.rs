@@ -0,0 +1,594 @@
+use lazy_static::lazy_static;
+
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::util::get_userinfo;
+
+lazy_static! {
+    static ref REGEX_LINE: Regex = Regex::new(r#"^ *(?P<time>[0-9]+:[0-9]{2}) *(?P<body>.*)"#).unwrap();
+    static ref REGEX_INIT: Regex = Regex::new(r#"^InitGame: (?P<serverinfo>.*)"#).unwrap();
+    static ref REGEX_SHUTDOWN: Regex = Regex::new(r#"^ShutdownGame:"#).unwrap();
+    static ref REGEX_CONNECT: Regex =
+        Regex::new(r#"^ClientConnect: (?P<id>[0-9]{1,2})(?:.*?(?P<ip>[0-9]{1,3}(?:\.[0-9]{1,3}){3}))?(?:.*?\((?P<guid>[0-9A-Fa-f]{32})\))?"#).unwrap();
+    static ref REGEX_DISCONNECT: Regex = Regex::new(r#"^ClientDisconnect: (?P<id>[0-9]{1,2})"#).unwrap();
+    static ref REGEX_BEGIN: Regex = Regex::new(r#"^ClientBegin: (?P<id>[0-9]{1,2})"#).unwrap();
+    static ref REGEX_USERINFO: Regex =
+        Regex::new(r#"^ClientUserinfoChanged: (?P<id>[0-9]{1,2}) (?P<userinfo>.*)"#).unwrap();
+    static ref REGEX_CHAT: Regex =
+        Regex::new(r#"^(?P<id>[0-9]{1,2}): say: (?P<username>.*): "(?P<message>.*)""#).unwrap();
+    static ref REGEX_TEAM_CHAT: Regex =
+        Regex::new(r#"^(?P<id>[0-9]{1,2}): sayteam: (?P<username>.*): "(?P<message>.*)""#).unwrap();
+    static ref REGEX_TELL: Regex =
+        Regex::new(r#"^(?P<id>[0-9]{1,2}): tell: (?P<username>.*) to (?P<target>.*): "(?P<message>.*)""#).unwrap();
+    static ref REGEX_KILL: Regex =
+        Regex::new(r#"^Kill: (?P<killer>[0-9]+) (?P<victim>[0-9]+) [0-9]+:.* by (?P<cause>\S+)"#).unwrap();
+    static ref REGEX_EXIT: Regex = Regex::new(r#"^Exit: (?P<reason>.*)"#).unwrap();
+    static ref REGEX_ROUND_START: Regex = Regex::new(r#"^RoundStart:(?: (?P<round>[0-9]+))?"#).unwrap();
+    static ref REGEX_ROUND_END: Regex = Regex::new(r#"^RoundEnd:(?: (?P<reason>.*))?"#).unwrap();
+    static ref REGEX_CLASS: Regex = Regex::new(r#"^ClassChange: (?P<id>[0-9]{1,2}) (?P<class>.*)"#).unwrap();
+}
+
+const MAX_CLIENTS: u8 = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    Line,
+    Time,
+    Client,
+}
+
+/// The time since the map started, as `MM:SS` in front of every log line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GameTime(u32);
+
+impl GameTime {
+    pub fn as_duration(self) -> Duration {
+        Duration::from_secs(u64::from(self.0))
+    }
+}
+
+impl FromStr for GameTime {
+    type Err = ParseError;
+    fn from_str(time: &str) -> Result<GameTime, ParseError> {
+        let mut parts = time.splitn(2, ':');
+        let minutes: u32 = parts
+            .next()
+            .and_then(|minutes| minutes.parse().ok())
+            .ok_or(ParseError::Time)?;
+        let seconds: u32 = parts
+            .next()
+            .filter(|seconds| seconds.len() == 2)
+            .and_then(|seconds| seconds.parse().ok())
+            .filter(|seconds| *seconds < 60)
+            .ok_or(ParseError::Time)?;
+        Ok(GameTime(minutes * 60 + seconds))
+    }
+}
+
+impl fmt::Display for GameTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+/// A player slot on the server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ClientId(u8);
+
+impl FromStr for ClientId {
+    type Err = ParseError;
+    fn from_str(id: &str) -> Result<ClientId, ParseError> {
+        match id.parse() {
+            Ok(id) if id < MAX_CLIENTS => Ok(ClientId(id)),
+            _ => Err(ParseError::Client),
+        }
+    }
+}
+
+impl fmt::Display for ClientId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    Init {
+        time: GameTime,
+        map: Option<String>,
+    },
+    Shutdown {
+        time: GameTime,
+    },
+    Connect {
+        time: GameTime,
+        id: ClientId,
+        ip: Option<String>,
+        guid: Option<String>,
+    },
+    Disconnect {
+        time: GameTime,
+        id: ClientId,
+    },
+    Begin {
+        time: GameTime,
+        id: ClientId,
+    },
+    UserinfoChanged {
+        time: GameTime,
+        id: ClientId,
+        userinfo: String,
+    },
+    Chat {
+        time: GameTime,
+        id: ClientId,
+        username: String,
+        message: String,
+    },
+    TeamChat {
+        time: GameTime,
+        id: ClientId,
+        username: String,
+        message: String,
+    },
+    Tell {
+        time: GameTime,
+        id: ClientId,
+        username: String,
+        target: String,
+        message: String,
+    },
+    /// The killer is `None` when the world did it, like a fall or a trigger.
+    Kill {
+        time: GameTime,
+        killer: Option<ClientId>,
+        victim: ClientId,
+        cause: String,
+    },
+    Exit {
+        time: GameTime,
+        reason: String,
+    },
+    RoundStart {
+        time: GameTime,
+        round: Option<u32>,
+    },
+    RoundEnd {
+        time: GameTime,
+        reason: Option<String>,
+    },
+    ClassChange {
+        time: GameTime,
+        id: ClientId,
+        class: String,
+    },
+}
+
+impl Event {
+    pub fn get_time(&self) -> GameTime {
+        match self {
+            Event::Init { time, .. }
+            | Event::Shutdown { time }
+            | Event::Connect { time, .. }
+            | Event::Disconnect { time, .. }
+            | Event::Begin { time, .. }
+            | Event::UserinfoChanged { time, .. }
+            | Event::Chat { time, .. }
+            | Event::TeamChat { time, .. }
+            | Event::Tell { time, .. }
+            | Event::Kill { time, .. }
+            | Event::Exit { time, .. }
+            | Event::RoundStart { time, .. }
+            | Event::RoundEnd { time, .. }
+            | Event::ClassChange { time, .. } => *time,
+        }
+    }
+}
+
+type Parser = fn(GameTime, &str) -> Result<Option<Event>, ParseError>;
+
+const PARSERS: [Parser; 14] = [
+    parse_init,
+    parse_shutdown,
+    parse_connect,
+    parse_disconnect,
+    parse_begin,
+    parse_userinfo,
+    parse_chat,
+    parse_team_chat,
+    parse_tell,
+    parse_kill,
+    parse_exit,
+    parse_round_start,
+    parse_round_end,
+    parse_class,
+];
+
+/// Parses one line of the game log, `ParseError::Line` when it is not an event
+/// the plugin knows about.
+impl FromStr for Event {
+    type Err = ParseError;
+    fn from_str(line: &str) -> Result<Event, ParseError> {
+        let captures = REGEX_LINE.captures(line).ok_or(ParseError::Line)?;
+        let time: GameTime = captures
+            .name("time")
+            .expect("could not get line 'time'")
+            .as_str()
+            .parse()?;
+        let body = captures
+            .name("body")
+            .expect("could not get line 'body'")
+            .as_str()
+            .trim_end();
+        for parse in PARSERS.iter() {
+            if let Some(event) = parse(time, body)? {
+                return Ok(event);
+            }
+        }
+        Err(ParseError::Line)
+    }
+}
+
+pub fn parse_init(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_INIT.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let serverinfo = captures
+        .name("serverinfo")
+        .expect("could not get init 'serverinfo'")
+        .as_str();
+    let map = get_userinfo(serverinfo).remove("mapname");
+    Ok(Some(Event::Init { time, map }))
+}
+
+pub fn parse_shutdown(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    match REGEX_SHUTDOWN.is_match(body) {
+        true => Ok(Some(Event::Shutdown { time })),
+        false => Ok(None),
+    }
+}
+
+pub fn parse_connect(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_CONNECT.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let id = captures
+        .name("id")
+        .expect("could not get connect 'id'")
+        .as_str()
+        .parse()?;
+    let ip = captures.name("ip").map(|ip| ip.as_str().to_owned());
+    let guid = captures.name("guid").map(|guid| guid.as_str().to_owned());
+    Ok(Some(Event::Connect { time, id, ip, guid }))
+}
+
+pub fn parse_disconnect(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_DISCONNECT.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let id = captures
+        .name("id")
+        .expect("could not get disconnect 'id'")
+        .as_str()
+        .parse()?;
+    Ok(Some(Event::Disconnect { time, id }))
+}
+
+pub fn parse_begin(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_BEGIN.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let id = captures
+        .name("id")
+        .expect("could not get begin 'id'")
+        .as_str()
+        .parse()?;
+    Ok(Some(Event::Begin { time, id }))
+}
+
+pub fn parse_userinfo(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_USERINFO.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let id = captures
+        .name("id")
+        .expect("could not get userinfo 'id'")
+        .as_str()
+        .parse()?;
+    let userinfo = captures
+        .name("userinfo")
+        .expect("could not get userinfo 'userinfo'")
+        .as_str()
+        .to_owned();
+    Ok(Some(Event::UserinfoChanged { time, id, userinfo }))
+}
+
+pub fn parse_chat(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_CHAT.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let id = captures
+        .name("id")
+        .expect("could not get chat 'id'")
+        .as_str()
+        .parse()?;
+    let username = captures
+        .name("username")
+        .expect("could not get chat 'username'")
+        .as_str()
+        .to_owned();
+    let message = captures
+        .name("message")
+        .expect("could not get chat 'message'")
+        .as_str()
+        .to_owned();
+    Ok(Some(Event::Chat {
+        time,
+        id,
+        username,
+        message,
+    }))
+}
+
+pub fn parse_team_chat(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_TEAM_CHAT.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let id = captures
+        .name("id")
+        .expect("could not get team chat 'id'")
+        .as_str()
+        .parse()?;
+    let username = captures
+        .name("username")
+        .expect("could not get team chat 'username'")
+        .as_str()
+        .to_owned();
+    let message = captures
+        .name("message")
+        .expect("could not get team chat 'message'")
+        .as_str()
+        .to_owned();
+    Ok(Some(Event::TeamChat {
+        time,
+        id,
+        username,
+        message,
+    }))
+}
+
+pub fn parse_tell(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_TELL.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let id = captures
+        .name("id")
+        .expect("could not get tell 'id'")
+        .as_str()
+        .parse()?;
+    let username = captures
+        .name("username")
+        .expect("could not get tell 'username'")
+        .as_str()
+        .to_owned();
+    let target = captures
+        .name("target")
+        .expect("could not get tell 'target'")
+        .as_str()
+        .to_owned();
+    let message = captures
+        .name("message")
+        .expect("could not get tell 'message'")
+        .as_str()
+        .to_owned();
+    Ok(Some(Event::Tell {
+        time,
+        id,
+        username,
+        target,
+        message,
+    }))
+}
+
+/// `Kill: <killer> <victim> <means>: <killer name> killed <victim name> by <MOD_NAME>`
+pub fn parse_kill(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_KILL.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let killer = captures
+        .name("killer")
+        .expect("could not get kill 'killer'")
+        .as_str()
+        .parse()
+        .ok();
+    let victim = captures
+        .name("victim")
+        .expect("could not get kill 'victim'")
+        .as_str()
+        .parse()?;
+    let cause = captures
+        .name("cause")
+        .expect("could not get kill 'cause'")
+        .as_str()
+        .to_owned();
+    Ok(Some(Event::Kill {
+        time,
+        killer,
+        victim,
+        cause,
+    }))
+}
+
+pub fn parse_exit(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_EXIT.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let reason = captures
+        .name("reason")
+        .expect("could not get exit 'reason'")
+        .as_str()
+        .to_owned();
+    Ok(Some(Event::Exit { time, reason }))
+}
+
+/// `RoundStart: <round>`, written by Movie Battles 2 when a round begins.
+pub fn parse_round_start(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_ROUND_START.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let round = captures
+        .name("round")
+        .and_then(|round| round.as_str().parse().ok());
+    Ok(Some(Event::RoundStart { time, round }))
+}
+
+/// `RoundEnd: <reason>`, written by Movie Battles 2 when a round is won.
+pub fn parse_round_end(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_ROUND_END.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let reason = captures
+        .name("reason")
+        .map(|reason| reason.as_str().to_owned());
+    Ok(Some(Event::RoundEnd { time, reason }))
+}
+
+/// `ClassChange: <id> <class>`, written by Movie Battles 2 when a player picks a class.
+pub fn parse_class(time: GameTime, body: &str) -> Result<Option<Event>, ParseError> {
+    let captures = match REGEX_CLASS.captures(body) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+    let id = captures
+        .name("id")
+        .expect("could not get class 'id'")
+        .as_str()
+        .parse()?;
+    let class = captures
+        .name("class")
+        .expect("could not get class 'class'")
+        .as_str()
+        .to_owned();
+    Ok(Some(Event::ClassChange { time, id, class }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(seconds: u32) -> GameTime {
+        GameTime(seconds)
+    }
+
+    fn client(id: u8) -> ClientId {
+        ClientId(id)
+    }
+
+    #[test]
+    fn game_time_is_parsed() {
+        assert_eq!("0:00".parse(), Ok(time(0)));
+        assert_eq!("12:34".parse(), Ok(time(754)));
+        assert_eq!(time(754).to_string(), "12:34");
+    }
+
+    #[test]
+    fn game_time_rejects_bad_seconds() {
+        assert_eq!("0:60".parse::<GameTime>(), Err(ParseError::Time));
+        assert_eq!("1:5".parse::<GameTime>(), Err(ParseError::Time));
+        assert_eq!("1:005".parse::<GameTime>(), Err(ParseError::Time));
+    }
+
+    #[test]
+    fn client_id_is_below_max_clients() {
+        assert_eq!("31".parse(), Ok(client(31)));
+        assert_eq!("32".parse::<ClientId>(), Err(ParseError::Client));
+        assert_eq!("1022".parse::<ClientId>(), Err(ParseError::Client));
+        assert_eq!(
+            "  0:01 ClientBegin: 32".parse::<Event>(),
+            Err(ParseError::Client)
+        );
+    }
+
+    #[test]
+    fn connect_with_ip_and_guid() {
+        let line =
+            "  1:02 ClientConnect: 3 [192.168.0.7] (0123456789ABCDEF0123456789abcdef) \"Padawan\"";
+        assert_eq!(
+            line.parse(),
+            Ok(Event::Connect {
+                time: time(62),
+                id: client(3),
+                ip: Some("192.168.0.7".to_owned()),
+                guid: Some("0123456789ABCDEF0123456789abcdef".to_owned()),
+            })
+        );
+    }
+
+    #[test]
+    fn connect_without_ip_and_guid() {
+        assert_eq!(
+            "  1:02 ClientConnect: 3".parse(),
+            Ok(Event::Connect {
+                time: time(62),
+                id: client(3),
+                ip: None,
+                guid: None,
+            })
+        );
+    }
+
+    #[test]
+    fn kill_by_the_world_has_no_killer() {
+        let line = "  3:10 Kill: 1022 4 34: <world> killed Padawan by MOD_FALLING";
+        assert_eq!(
+            line.parse(),
+            Ok(Event::Kill {
+                time: time(190),
+                killer: None,
+                victim: client(4),
+                cause: "MOD_FALLING".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn chat_is_parsed() {
+        assert_eq!(
+            "  0:15 2: say: ^1Vader: \"vote map mb2_dotf\"".parse(),
+            Ok(Event::Chat {
+                time: time(15),
+                id: client(2),
+                username: "^1Vader".to_owned(),
+                message: "vote map mb2_dotf".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_lines_are_ignored() {
+        for line in &[
+            "",
+            "------------------------------------------------------------",
+            "  0:05 Item: 2 weapon_blaster",
+            "  0:05 score: 10  ping: 50  client: 1 Padawan",
+            "ClientBegin: 1",
+        ] {
+            assert_eq!(line.parse::<Event>(), Err(ParseError::Line), "{}", line);
+        }
+    }
+}
@@ -1,363 +1,1528 @@
 mod ballot;
+mod clock;
 mod console;
 mod cooldown;
+mod event;
+mod messages;
+mod nomination;
+mod players;
+mod poll;
+mod rtv;
+mod sanction;
 mod scanner;
+mod settings;
+mod store;
 mod util;
+mod watcher;
 
 use lazy_static::lazy_static;
 
-use std::collections::{HashMap, HashSet};
-use std::thread::sleep;
-use std::time::Duration;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::mem;
+use std::rc::Rc;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
 
 use clap::{crate_version, App, Arg};
 use regex::Regex;
 
 use ballot::{Ballot, VoteError, VoteResult};
-use console::Console;
-use scanner::{Event, Scanner};
+use console::{CommandSink, DryRun, Queue, RconConsole};
+use event::{Event, GameTime};
+use messages::Messages;
+use players::{Player, PlayerError, Players};
+use poll::{Method, Poll, PollError};
+use rtv::{RockTheVote, RtvError};
+use sanction::Sanctions;
+use scanner::Scanner;
+use settings::Settings;
+use store::{to_timestamp, Outcome, Record, ServerStore, Store};
 
-use util::{get_maplist, get_server_info};
+use util::{shuffle, strip_colors};
 
 fn main() {
     let matches = App::new("Democracy")
         .version(crate_version!())
         .author("Goatfoot")
         .about("A voting plugin for Movie Battles 2")
+        .arg(
+            Arg::with_name("config")
+                .short("f")
+                .long("config")
+                .value_name("CONFIG")
+                .help("Sets the settings file (TOML, YAML or JSON)"),
+        )
         .arg(
             Arg::with_name("maps")
                 .short("m")
                 .long("maps")
                 .value_name("MAPS")
-                .help("Sets the file that contains the map list")
-                .default_value("./maps.txt"),
+                .help("Sets the file that contains the map list"),
         )
         .arg(
             Arg::with_name("rcon")
                 .short("r")
                 .long("rcon")
                 .value_name("RCON")
-                .help("Sets the rcon password")
-                .default_value("password"),
+                .help("Sets the rcon password"),
         )
         .arg(
-            Arg::with_name("hostip")
+            Arg::with_name("host_ip")
                 .short("i")
                 .long("host-ip")
                 .value_name("HOST IP")
-                .help("Sets the host IP")
-                .default_value("127.0.0.1"),
+                .help("Sets the host name or IP address"),
         )
         .arg(
-            Arg::with_name("hostport")
+            Arg::with_name("host_port")
                 .short("p")
                 .long("host-port")
                 .value_name("HOST PORT")
-                .help("Sets the host port")
-                .default_value("29070"),
+                .help("Sets the host port"),
+        )
+        .arg(
+            Arg::with_name("bind")
+                .short("b")
+                .long("bind")
+                .value_name("ADDRESS")
+                .help("Sets the local address and port of the rcon socket"),
         )
         .arg(
-            Arg::with_name("clientport")
+            Arg::with_name("client_port")
                 .short("c")
                 .long("client-port")
                 .value_name("CLIENT PORT")
-                .help("Sets the client port")
-                .default_value("3400"),
+                .help("Sets the local port of the rcon socket, deprecated for --bind"),
         )
         .arg(
             Arg::with_name("log")
                 .short("l")
                 .long("log")
                 .value_name("LOG")
-                .help("Sets the game log file")
-                .default_value("./games.log"),
+                .help("Sets the game log file, - to read it from standard input"),
         )
         .arg(
             Arg::with_name("interval")
                 .short("t")
                 .long("interval")
                 .value_name("INTERVAL")
-                .help("Sets the update interval in second")
-                .default_value("1"),
+                .help("Sets the update interval in second"),
         )
         .arg(
             Arg::with_name("timeout")
                 .short("o")
                 .long("timeout")
                 .value_name("TIMEOUT")
-                .help("Sets the timeout for server command in millisecond")
-                .default_value("100"),
+                .help("Sets the timeout for server command in millisecond"),
         )
         .arg(
-            Arg::with_name("votingduration")
+            Arg::with_name("voting_duration")
                 .short("d")
                 .long("votingduration")
                 .value_name("VOTING DURATION")
-                .help("Sets the voting duration")
-                .default_value("30"),
+                .help("Sets the voting duration"),
         )
         .arg(
-            Arg::with_name("playercooldown")
+            Arg::with_name("player_cooldown")
                 .short("C")
                 .long("playercooldown")
                 .value_name("PLAYER COOLDOWN")
-                .help("Sets the player cooldown")
-                .default_value("30"),
+                .help("Sets the player cooldown"),
         )
         .arg(
             Arg::with_name("target")
                 .short("x")
                 .long("target")
                 .value_name("TARGET")
-                .help("Sets the voting target ratio")
-                .default_value("0.6"),
+                .help("Sets the voting target ratio"),
+        )
+        .arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .value_name("LOG")
+                .help("Plays a recorded game log and prints the commands instead of sending them"),
+        )
+        .arg(
+            Arg::with_name("replay_speed")
+                .long("replay-speed")
+                .value_name("SPEED")
+                .help("Sets how many times faster than real time to replay, 0 for no waiting"),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .help("Follows the game log but prints the commands instead of sending them"),
         )
         .get_matches();
 
-    let maps = matches.value_of("maps").unwrap_or_default();
-    let rcon = matches.value_of("rcon").unwrap_or_default();
-    let hostip = matches.value_of("hostip").unwrap_or_default();
-    let hostport: u16 = matches
-        .value_of("hostport")
-        .unwrap_or_default()
-        .parse()
-        .expect("cannot read host port");
-    let clientport: u16 = matches
-        .value_of("clientport")
-        .unwrap_or_default()
-        .parse()
-        .expect("cannot read client port");
-    let log = matches.value_of("log").unwrap_or_default();
-    let interval: u64 = matches
-        .value_of("interval")
-        .unwrap_or_default()
-        .parse()
-        .expect("cannot read interval");
-    let timeout: u64 = matches
-        .value_of("timeout")
-        .unwrap_or_default()
-        .parse()
-        .expect("cannot read timeour");
-    let voting_duration: u64 = matches
-        .value_of("votingduration")
-        .unwrap_or_default()
-        .parse()
-        .expect("cannot read interval");
-    let player_cooldown: u64 = matches
-        .value_of("playercooldown")
-        .unwrap_or_default()
-        .parse()
-        .expect("cannot read timeout");
-    let target: f32 = matches
-        .value_of("target")
-        .unwrap_or_default()
-        .parse()
-        .expect("cannot read target");
-
-    let mut scanner = Scanner::new(log);
-    let console = Console::new(
-        rcon.to_owned(),
-        hostip,
-        hostport,
-        clientport,
-        Duration::from_millis(timeout),
+    let settings = Settings::new(&matches).expect("cannot read settings");
+
+    let interval = Duration::from_secs(settings.interval);
+    let servers = settings.get_servers();
+    if let Some(path) = matches.value_of("replay") {
+        let speed: f32 = matches
+            .value_of("replay_speed")
+            .map(|speed| speed.parse().expect("replay speed is not a number"))
+            .unwrap_or(0f32);
+        let mut system = create_system(
+            DryRun::new(&servers[0].name),
+            servers[0],
+            open_memory_store(servers[0]),
+        );
+        replay_log(&mut system, path, speed, interval);
+        println!(
+            "{} command(s) would have been sent",
+            system.get_console().get_commands().len()
+        );
+        return;
+    }
+    if matches.is_present("dry_run") {
+        let systems = servers
+            .iter()
+            .map(|server| {
+                create_system(DryRun::new(&server.name), server, open_memory_store(server))
+            })
+            .collect();
+        follow_logs(systems, &servers, &settings);
+    }
+    let mut stores = HashMap::new();
+    let systems = servers
+        .iter()
+        .map(|server| {
+            let mut console = RconConsole::new(
+                server.rcon.to_owned(),
+                &server.host_ip,
+                server.host_port,
+                server.get_bind().as_deref(),
+                Duration::from_millis(server.timeout),
+            )
+            .unwrap_or_else(|error| panic!("cannot open rcon socket: {}", error));
+            console
+                .check()
+                .unwrap_or_else(|error| panic!("cannot use rcon: {}", error));
+            let console = Queue::new(console, server.rcon_rate, server.rcon_retries);
+            create_system(console, server, open_store(&mut stores, server))
+        })
+        .collect();
+    follow_logs(systems, &servers, &settings);
+}
+
+/// Opens each store file once, so the servers that name the same file share it.
+fn open_store(
+    stores: &mut HashMap<String, Rc<RefCell<Store>>>,
+    settings: &Settings,
+) -> ServerStore {
+    let store = stores.entry(settings.store.to_owned()).or_insert_with(|| {
+        let store =
+            Store::open(&settings.store, settings.history_limit).expect("cannot open store");
+        Rc::new(RefCell::new(store))
+    });
+    ServerStore::new(Rc::clone(store), &settings.name)
+}
+
+/// A store that is neither read nor written, for replays and dry runs.
+fn open_memory_store(settings: &Settings) -> ServerStore {
+    let store = Store::in_memory(settings.history_limit);
+    ServerStore::new(Rc::new(RefCell::new(store)), &settings.name)
+}
+
+/// Sets up the voting logic of one server.
+fn create_system<C: CommandSink>(
+    console: C,
+    settings: &Settings,
+    mut store: ServerStore,
+) -> System<C> {
+    let nominations = settings
+        .get_nominations()
+        .expect("can't read nomination list");
+    let mut ballot = Ballot::new(
+        Duration::from_secs(settings.voting_duration),
+        Duration::from_secs(settings.player_cooldown),
+        settings.target,
+        nominations,
+        settings.map_rotation_cooldown,
     );
-    let mut nominations = HashMap::new();
-    nominations.insert(
-        "map".to_owned(),
-        get_maplist(maps).expect("can't find map list"),
+    for (identity, expiry) in store.get_cooldowns() {
+        ballot.set_user_cooldown(&identity, expiry);
+    }
+    for map in store.get_recent_maps() {
+        ballot.add_played_map(&map);
+    }
+    let mut sanctions = Sanctions::new(
+        &settings.kick_command,
+        &settings.ban_command,
+        &settings.unban_command,
+        Duration::from_secs(settings.max_ban_duration * 60),
     );
-    let mut modes = HashSet::new();
-    modes.insert("0".to_owned());
-    modes.insert("1".to_owned());
-    modes.insert("2".to_owned());
-    modes.insert("3".to_owned());
-    modes.insert("4".to_owned());
-    nominations.insert("mode".to_owned(), modes);
-    let ballot = Ballot::new(
-        Duration::from_secs(voting_duration),
-        Duration::from_secs(player_cooldown),
-        target,
-        nominations,
+    for (ip, expiry) in store.get_bans() {
+        sanctions.restore_ban(&ip, expiry);
+    }
+    let players = Players::new(settings.spectators_vote);
+    let poll = Poll::new(
+        &settings.poll_nomination,
+        Duration::from_secs(settings.poll_duration),
+        Method::from_name(&settings.poll_method).expect("unknown poll method"),
+        settings.poll_size,
+        settings.poll_on_shutdown,
     );
-    let mut system = System::new(console, ballot);
+    let rtv = RockTheVote::new(
+        settings.rtv_ratio,
+        Duration::from_secs(settings.rtv_cooldown),
+    );
+    let messages = settings.get_messages().expect("can't read messages");
+    System::new(
+        console, ballot, poll, rtv, players, sanctions, store, messages,
+    )
+}
+
+/// Reads every server's log on its own thread and handles the events of all of
+/// them in one loop.
+fn follow_logs<C: CommandSink>(
+    mut systems: Vec<System<C>>,
+    servers: &[&Settings],
+    settings: &Settings,
+) -> ! {
+    let interval = Duration::from_secs(settings.interval);
+    let (sender, receiver) = channel();
+    for (index, server) in servers.iter().enumerate() {
+        let scanner = Scanner::new(&server.log, server.watch);
+        let sender = sender.clone();
+        let interval = Duration::from_secs(server.interval);
+        thread::spawn(move || scan_log(scanner, index, sender, interval));
+    }
+    for system in systems.iter_mut() {
+        system.load_players();
+    }
+    let mut next_update = Instant::now() + interval;
     loop {
+        match receiver.recv_timeout(next_update.saturating_duration_since(Instant::now())) {
+            Ok((index, event)) => systems[index].handle_event(event),
+            Err(RecvTimeoutError::Timeout) => {
+                for system in systems.iter_mut() {
+                    system.update();
+                }
+                next_update = Instant::now() + interval;
+            }
+            Err(RecvTimeoutError::Disconnected) => sleep(interval),
+        }
+        // A ban voted on one server is applied on all of them.
+        for index in 0..systems.len() {
+            let bans = systems[index].take_bans();
+            if !settings.share_bans {
+                continue;
+            }
+            for (ip, duration) in bans {
+                for (other, system) in systems.iter_mut().enumerate() {
+                    if other != index {
+                        system.ban(&ip, duration);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn scan_log(
+    mut scanner: Scanner,
+    index: usize,
+    sender: Sender<(usize, Event)>,
+    interval: Duration,
+) {
+    loop {
+        let mut failure = None;
         for event in scanner.events() {
-            system.handle_event(event);
+            match event {
+                Ok(event) => {
+                    if sender.send((index, event)).is_err() {
+                        return;
+                    }
+                }
+                Err(error) => failure = Some(error),
+            }
         }
-        system.check_vote_result(true);
-        sleep(Duration::from_secs(interval));
+        if let Some(error) = failure {
+            eprintln!(
+                "could not read log file ({} read error(s) so far): {}",
+                scanner.get_read_errors(),
+                error
+            );
+        }
+        scanner.wait(interval);
+    }
+}
+
+/// Feeds a recorded log through the voting logic. The time between events is taken
+/// from their timestamps and passes on the replay clock, so votes end as they did.
+fn replay_log<C: CommandSink>(system: &mut System<C>, path: &str, speed: f32, interval: Duration) {
+    let file = File::open(path).expect("cannot open replay log");
+    let mut previous: Option<GameTime> = None;
+    for line in BufReader::new(file).split(b'\n') {
+        let line = line.expect("error reading replay log");
+        let event = match String::from_utf8_lossy(&line).parse::<Event>() {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+        let time = event.get_time();
+        // The game time starts over on every map.
+        if let Some(previous) = previous {
+            if time > previous {
+                pass_time(
+                    system,
+                    time.as_duration() - previous.as_duration(),
+                    speed,
+                    interval,
+                );
+            }
+        }
+        previous = Some(time);
+        match &event {
+            Event::Chat {
+                username, message, ..
+            }
+            | Event::TeamChat {
+                username, message, ..
+            } => println!("{} {}: {}", time, strip_colors(username), message),
+            _ => {}
+        }
+        system.handle_event(event);
+    }
+    while system.is_busy() {
+        pass_time(system, interval, speed, interval);
     }
 }
 
+/// Waits `duration / speed` for real and moves the replay clock over the rest.
+fn pass_time<C: CommandSink>(
+    system: &mut System<C>,
+    duration: Duration,
+    speed: f32,
+    interval: Duration,
+) {
+    let mut remaining = duration;
+    while remaining > Duration::from_secs(0) {
+        let step = remaining.min(interval);
+        let real = match speed > 0f32 {
+            true => step.div_f32(speed),
+            false => Duration::from_secs(0),
+        };
+        sleep(real);
+        clock::advance(step.checked_sub(real).unwrap_or_default());
+        system.update();
+        remaining -= step;
+    }
+}
+
+const KICK: &str = "kick";
+const TEMPBAN: &str = "tempban";
+
 lazy_static! {
+    static ref REGEX_CHAT_KICK: Regex = Regex::new(r#"^vote kick (?P<target>.+)"#).unwrap();
+    static ref REGEX_CHAT_TEMPBAN: Regex =
+        Regex::new(r#"^vote tempban (?P<target>\S+) (?P<minutes>[0-9]+)"#).unwrap();
     static ref REGEX_CHAT_PROPOSE: Regex =
-        Regex::new(r#"^vote (?P<type>map|mode) (?P<input>.*)"#).unwrap();
+        Regex::new(r#"^vote (?P<type>\S+) (?P<input>.*)"#).unwrap();
     static ref REGEX_CHAT_VOTE: Regex = Regex::new(r#"^(?P<vote>yay|nay)"#).unwrap();
+    static ref REGEX_CHAT_RTV: Regex = Regex::new(r#"^ *rtv *$"#).unwrap();
+    static ref REGEX_CHAT_NOMINATE: Regex = Regex::new(r#"^nominate (?P<input>.+)"#).unwrap();
+    static ref REGEX_CHAT_CHOICE: Regex =
+        Regex::new(r#"^ *(?P<choices>[0-9]+(?: +[0-9]+)*) *$"#).unwrap();
 }
 
-pub struct System {
-    console: Console,
+/// The running motion as it was announced, for the messages about its result.
+#[derive(Default)]
+struct Motion {
+    text: String,
+    r#type: String,
+    value: String,
+    proposer: String,
+}
+
+impl Motion {
+    fn get_values(&self) -> [(&str, &str); 4] {
+        [
+            ("motion", &self.text),
+            ("type", &self.r#type),
+            ("value", &self.value),
+            ("player", &self.proposer),
+        ]
+    }
+}
+
+pub struct System<C: CommandSink> {
+    console: C,
     ballot: Ballot,
+    poll: Poll,
+    rtv: RockTheVote,
+    players: Players,
+    sanctions: Sanctions,
+    store: ServerStore,
+    messages: Messages,
+    bans: Vec<(String, Duration)>,
+    target_ip: Option<String>,
+    motion: Motion,
 }
 
-impl System {
-    pub fn new(console: Console, ballot: Ballot) -> System {
+impl<C: CommandSink> System<C> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        console: C,
+        ballot: Ballot,
+        poll: Poll,
+        rtv: RockTheVote,
+        players: Players,
+        sanctions: Sanctions,
+        store: ServerStore,
+        messages: Messages,
+    ) -> System<C> {
         System {
-            console: console,
-            ballot: ballot,
+            console,
+            ballot,
+            poll,
+            rtv,
+            players,
+            sanctions,
+            store,
+            messages,
+            bans: Vec::new(),
+            target_ip: None,
+            motion: Motion::default(),
+        }
+    }
+
+    pub fn get_console(&self) -> &C {
+        &self.console
+    }
+
+    fn svsay(&mut self, message: &[u8]) {
+        if let Err(error) = self.console.svsay(message) {
+            eprintln!(
+                "could not say '{}': {}",
+                String::from_utf8_lossy(message),
+                error
+            );
+        }
+    }
+
+    /// Says a message of the catalog, unless it was emptied there.
+    fn say(&mut self, name: &str, values: &[(&str, &str)]) {
+        let message = self.messages.format(name, values);
+        if !message.is_empty() {
+            self.svsay(message.as_bytes());
+        }
+    }
+
+    fn rcon_send(&mut self, command: &[u8]) {
+        if let Err(error) = self.console.rcon_post(command) {
+            eprintln!(
+                "could not run '{}': {}",
+                String::from_utf8_lossy(command),
+                error
+            );
         }
     }
 
+    /// Picks up the players that were on the server before the plugin started,
+    /// since the log is only read from its end.
+    pub fn load_players(&mut self) {
+        let status = match self.console.status() {
+            Ok(status) => status,
+            Err(error) => {
+                eprintln!("could not get the server status: {}", error);
+                return;
+            }
+        };
+        if let Some(map) = &status.map {
+            self.ballot.add_played_map(map);
+            self.save();
+        }
+        for player in status.players {
+            if self.players.get(&player.slot).is_none() {
+                self.players.add(
+                    &player.slot,
+                    &player.name,
+                    player.get_ip(),
+                    player.is_bot(),
+                    player.ping.is_some(),
+                );
+            }
+        }
+        self.refresh_player_count();
+    }
+
+    /// Runs everything that depends on time passing rather than on the log.
+    pub fn update(&mut self) {
+        self.check_vote_result(true);
+        self.check_poll_result();
+        self.check_sanctions();
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.ballot.is_voting() || self.poll.is_polling()
+    }
+
     pub fn handle_event(&mut self, event: Event) {
         match event {
-            Event::Init(minute, second) => {
+            Event::Init { map, .. } => {
+                if let Some(map) = map {
+                    self.ballot.add_played_map(&map);
+                    self.save();
+                }
+                self.poll.clear_nominations();
+                self.rtv.reset();
+                self.refresh_player_count();
+            }
+            Event::Shutdown { .. } => {
+                if self.poll.is_started_on_shutdown() {
+                    self.start_poll();
+                }
+            }
+            Event::Connect { id, ip, guid, .. } => {
+                self.players.connect(&id.to_string(), ip, guid);
+            }
+            Event::Disconnect { id, .. } => {
+                let id = id.to_string();
+                let voter = self.players.get_voter(&id);
+                let guid = self.players.get(&id).and_then(Player::get_guid).is_some();
+                self.players.disconnect(&id);
+                // A tempban still goes ahead, the address was kept when the vote opened.
+                if self.ballot.is_excluded(&voter) {
+                    if self.ballot.get_type() == KICK {
+                        self.say("player_left", &[]);
+                        self.finish_voting(Outcome::Dismissed);
+                    } else if !guid {
+                        // The slot is all that told the target apart, and the next
+                        // player to get it must neither be left out nor kicked.
+                        self.ballot.clear_excluded();
+                    }
+                }
+                self.ballot.unvote(&voter).ok();
+                self.poll.unvote(&voter);
+                self.poll.unnominate(&voter);
+                self.rtv.remove(&voter);
+                self.refresh_player_count();
+                self.check_rtv();
+            }
+            Event::Begin { id, .. } => {
+                self.players.begin(&id.to_string());
+                self.refresh_player_count();
+            }
+            Event::UserinfoChanged { id, userinfo, .. } => {
+                self.players.update(&id.to_string(), &userinfo);
+                // Joining or leaving the spectators changes who may vote.
                 self.refresh_player_count();
             }
-            Event::Shutdown(minute, second) => {}
-            Event::Connect(minute, second, id) => {}
-            Event::Disconnect(minute, second, id) => {
-                self.ballot.unvote(&id);
-                self.ballot.decrement_voters();
+            Event::Chat {
+                id,
+                username,
+                message,
+                ..
+            } => {
+                self.handle_message(&id.to_string(), &username, &message);
             }
-            Event::Chat(minute, second, id, username, message) => {
-                self.handle_message(&id, &username, &message);
+            Event::TeamChat {
+                id,
+                username,
+                message,
+                ..
+            } => {
+                self.handle_message(&id.to_string(), &username, &message);
             }
+            Event::Tell { .. }
+            | Event::Kill { .. }
+            | Event::Exit { .. }
+            | Event::RoundStart { .. }
+            | Event::RoundEnd { .. }
+            | Event::ClassChange { .. } => {}
         }
     }
 
     pub fn check_vote_result(&mut self, majority_result: bool) {
         match self.ballot.get_result(majority_result) {
             Ok(result) => {
-                match result {
+                let motion = mem::take(&mut self.motion);
+                let outcome = match result {
                     VoteResult::Yay(r#type, input) => {
-                        self.console.svsay(b"Yay vote majority, motion granted.");
-                        match r#type.as_str() {
-                            "map" => {
-                                self.console.map(input.as_bytes());
-                            }
-                            "mode" => {
-                                self.console.mbmode(input.as_bytes());
-                            }
-                            _ => {}
-                        }
+                        self.say("granted", &motion.get_values());
+                        self.execute(&r#type, &input);
+                        Outcome::Granted
                     }
                     VoteResult::Nay => {
-                        self.console.svsay(b"Nay vote majority, motion denied.");
+                        self.say("denied", &motion.get_values());
+                        Outcome::Denied
                     }
                     VoteResult::None => {
-                        self.console.svsay(b"Voting deadlock, motion denied.");
+                        self.say("deadlock", &motion.get_values());
+                        Outcome::Deadlock
                     }
+                };
+                self.finish_voting(outcome);
+            }
+            Err(VoteError::Voters) => {
+                self.finish_voting(Outcome::Dismissed);
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Offers the most nominated candidates, topped up with random ones, to choose from.
+    pub fn start_poll(&mut self) {
+        if self.ballot.is_voting() || self.poll.is_polling() {
+            return;
+        }
+        let (name, candidates) = match self.get_poll_candidates() {
+            Some(candidates) => candidates,
+            None => return,
+        };
+        if self.poll.start_polling(candidates).is_err() {
+            return;
+        }
+        let message = match self.poll.get_method() {
+            Method::Plurality => "poll_plurality",
+            Method::InstantRunoff => "poll_instant_runoff",
+        };
+        self.say(message, &[("type", &name)]);
+        let candidates: Vec<String> = self.poll.get_candidates().to_vec();
+        for (index, candidate) in candidates.iter().enumerate() {
+            let number = (index + 1).to_string();
+            self.say(
+                "poll_candidate",
+                &[("number", &number), ("value", candidate)],
+            );
+        }
+    }
+
+    /// Picks the candidates of a map vote along with the name of their category,
+    /// `None` when the poll category is not configured.
+    fn get_poll_candidates(&self) -> Option<(String, Vec<String>)> {
+        let nomination = self.ballot.get_nomination(self.poll.get_nomination())?;
+        let mut candidates: Vec<String> = self
+            .poll
+            .get_nominations()
+            .into_iter()
+            .filter(|candidate| {
+                self.ballot
+                    .check_proposal(nomination.get_keyword(), candidate)
+                    .is_ok()
+            })
+            .collect();
+        let mut others: Vec<String> = nomination
+            .get_values()
+            .iter()
+            .filter(|value| !candidates.contains(value))
+            .filter(|value| {
+                self.ballot
+                    .check_proposal(nomination.get_keyword(), value)
+                    .is_ok()
+            })
+            .cloned()
+            .collect();
+        shuffle(&mut others);
+        candidates.extend(others);
+        candidates.truncate(self.poll.get_size());
+        Some((nomination.get_name().to_owned(), candidates))
+    }
+
+    pub fn check_poll_result(&mut self) {
+        match self.poll.get_result() {
+            Ok(Some(winner)) => {
+                let keyword = self.poll.get_nomination().to_owned();
+                let name = self.get_nomination_name(&keyword);
+                self.say("poll_won", &[("type", &name), ("value", &winner)]);
+                self.poll.stop_polling().ok();
+                self.execute(&keyword, &winner);
+            }
+            Ok(None) => {
+                self.say("poll_empty", &[]);
+                self.poll.stop_polling().ok();
+            }
+            Err(_) => {}
+        }
+    }
+
+    fn finish_voting(&mut self, outcome: Outcome) {
+        let (yays, nays) = self.ballot.get_votes();
+        self.store.record(Record {
+            proposer: self.ballot.get_proposer().to_owned(),
+            r#type: self.ballot.get_type(),
+            value: self.ballot.get_proposal().to_owned(),
+            yays,
+            nays,
+            voters: self.ballot.get_player_votes().clone(),
+            outcome,
+            started: to_timestamp(self.ballot.get_start_time()),
+            finished: to_timestamp(clock::now()),
+            server: String::new(),
+        });
+        self.ballot.stop_voting().ok();
+        self.target_ip = None;
+        self.motion = Motion::default();
+        self.save();
+    }
+
+    fn save(&mut self) {
+        self.store.set_cooldowns(self.ballot.get_cooldowns());
+        self.store.set_bans(self.sanctions.get_bans());
+        self.store.set_recent_maps(self.ballot.get_recent_maps());
+        if let Err(error) = self.store.save() {
+            eprintln!("could not save store: {}", error);
+        }
+    }
+
+    pub fn ban(&mut self, ip: &str, duration: Duration) {
+        let command = self.sanctions.ban(ip, duration);
+        self.rcon_send(command.as_bytes());
+        self.save();
+    }
+
+    /// Hands over the bans voted since the last call, for the other servers.
+    pub fn take_bans(&mut self) -> Vec<(String, Duration)> {
+        self.bans.drain(..).collect()
+    }
+
+    pub fn check_sanctions(&mut self) {
+        let commands = self.sanctions.get_expired();
+        if commands.is_empty() {
+            return;
+        }
+        for command in commands {
+            self.rcon_send(command.as_bytes());
+        }
+        self.save();
+    }
+
+    fn execute(&mut self, r#type: &str, input: &str) {
+        match r#type {
+            KICK => {
+                let command = self.sanctions.get_kick_command(input);
+                self.rcon_send(command.as_bytes());
+            }
+            TEMPBAN => {
+                let mut arguments = input.split(' ');
+                let id = arguments.next().unwrap_or_default();
+                let minutes: u64 = arguments.next().unwrap_or_default().parse().unwrap_or(0);
+                let mut kicked = Vec::new();
+                // The target may have left, and someone else may have the slot now.
+                if self.ballot.is_excluded(&self.players.get_voter(id)) {
+                    kicked.push(id.to_owned());
+                }
+                if let Some(ip) = self.target_ip.take() {
+                    // A ban does not drop the clients already connected from the
+                    // address, like a target who came back in another slot.
+                    for slot in self.players.find_by_ip(&ip) {
+                        if !kicked.contains(&slot) {
+                            kicked.push(slot);
+                        }
+                    }
+                    let duration = Duration::from_secs(minutes * 60);
+                    self.ban(&ip, duration);
+                    self.bans.push((ip, duration));
+                }
+                for slot in kicked {
+                    let command = self.sanctions.get_kick_command(&slot);
+                    self.rcon_send(command.as_bytes());
                 }
-                self.ballot.stop_voting();
             }
-            Err(error) => match error {
-                VoteError::Voters => {
-                    self.ballot.stop_voting();
+            _ => {
+                if let Some(nomination) = self.ballot.get_nomination(r#type) {
+                    let command = nomination.get_command(input);
+                    self.rcon_send(command.as_bytes());
                 }
-                _ => {}
-            },
+            }
         }
     }
 
+    /// Counts the voters, leaving out the target of a running motion.
     fn refresh_player_count(&mut self) {
-        self.ballot
-            .set_voters(get_server_info(&mut self.console).unwrap());
+        let voters = self.players.get_voter_count(self.ballot.get_excluded());
+        self.ballot.set_voters(voters).ok();
     }
 
-    fn handle_message(&mut self, id: &String, username: &String, message: &String) {
-        match REGEX_CHAT_PROPOSE.captures(message) {
-            Some(captures) => {
-                let r#type = captures
-                    .name("type")
-                    .expect("could not get propose 'type'")
-                    .as_str()
-                    .to_owned();
-                let input = captures
-                    .name("input")
-                    .expect("could not get propose 'type'")
+    fn handle_message(&mut self, id: &str, username: &str, message: &str) {
+        // Only the players counted as voters take part, or the rest could
+        // outvote them.
+        if !self.players.is_voter(id) {
+            if self.is_voting_command(message) {
+                self.say("not_voter", &[("player", username)]);
+            }
+            return;
+        }
+        let identity = self.players.get_identity(id);
+        let voter = self.players.get_voter(id);
+        if self.poll.is_polling() {
+            if let Some(captures) = REGEX_CHAT_CHOICE.captures(message) {
+                let choices: Vec<usize> = captures
+                    .name("choices")
+                    .expect("could not get choice 'choices'")
                     .as_str()
-                    .to_owned();
-                match self.ballot.start_voting(id, &r#type, &input) {
-                    Ok(_) => {
-                        self.refresh_player_count();
-                        self.console
-                            .svsay(
-                                &[
-                                    self.ballot.get_type().as_bytes(),
-                                    b" '",
-                                    input.as_bytes(),
-                                    b"' is nominated!",
-                                ]
-                                .concat(),
-                            )
-                            .unwrap();
-                        self.console.svsay(b"type 'yay' to vote yes").unwrap();
-                        self.console.svsay(b"type 'nay' to vote no").unwrap();
-                        let (yay, nay) = self.ballot.get_requirements();
-                        self.console
-                            .svsay(format!("{} yay vote(s) needed for motion", yay).as_bytes())
-                            .unwrap();
-                        self.console
-                            .svsay(format!("{} nay vote(s) needed to deny", nay).as_bytes())
-                            .unwrap();
-                        self.ballot.vote(id, true);
-                        self.check_vote_result(false);
-                    }
-                    Err(error) => match error {
-                        VoteError::Cooldown(duration) => {
-                            self.console
-                                .svsay(
-                                    format!(
-                                        "User '{}' is in cooldown for {:.2} second!",
-                                        username, duration
-                                    )
-                                    .as_bytes(),
-                                )
-                                .unwrap();
-                        }
-                        VoteError::Progress => {
-                            self.console
-                                .svsay(b"Voting is currently in progress!")
-                                .unwrap();
-                        }
-                        VoteError::Nomination => {
-                            self.console
-                                .svsay(format!("Map '{}' is not on the list!", input).as_bytes())
-                                .unwrap();
-                        }
-                        _ => {}
-                    },
+                    .split_whitespace()
+                    .map(|choice| choice.parse().unwrap_or(0))
+                    .collect();
+                if let Err(PollError::Choice) = self.poll.vote(&voter, &choices) {
+                    let count = self.poll.get_candidates().len().to_string();
+                    self.say("poll_choice", &[("needed", &count)]);
                 }
+                return;
             }
-            None => {}
-        }
-        match REGEX_CHAT_VOTE.captures(message) {
-            Some(captures) => {
-                let vote = captures
-                    .name("vote")
-                    .expect("could not get propose 'type'")
-                    .as_str();
-                match match vote {
-                    "yay" => self.ballot.vote(id, true),
-                    "nay" => self.ballot.vote(id, false),
-                    _ => Err(VoteError::Progress),
-                } {
-                    Ok(_) => {
-                        self.print_requirements();
-                        self.check_vote_result(false);
-                    }
-                    Err(_) => {}
+            if REGEX_CHAT_PROPOSE.is_match(message) {
+                self.say("in_progress", &[]);
+                return;
+            }
+        }
+        if REGEX_CHAT_RTV.is_match(message) {
+            self.rock_the_vote(&voter, username);
+        } else if let Some(captures) = REGEX_CHAT_NOMINATE.captures(message) {
+            let input = captures
+                .name("input")
+                .expect("could not get nominate 'input'")
+                .as_str()
+                .to_owned();
+            self.nominate(&voter, username, &input);
+        } else if let Some(captures) = REGEX_CHAT_KICK.captures(message) {
+            let target = captures
+                .name("target")
+                .expect("could not get kick 'target'")
+                .as_str();
+            self.propose_sanction(id, username, target, None);
+        } else if let Some(captures) = REGEX_CHAT_TEMPBAN.captures(message) {
+            let target = captures
+                .name("target")
+                .expect("could not get tempban 'target'")
+                .as_str();
+            let minutes = captures
+                .name("minutes")
+                .expect("could not get tempban 'minutes'")
+                .as_str()
+                .parse()
+                .unwrap_or(u64::MAX);
+            self.propose_sanction(id, username, target, Some(minutes));
+        } else if let Some(captures) = REGEX_CHAT_PROPOSE.captures(message) {
+            let r#type = captures
+                .name("type")
+                .expect("could not get propose 'type'")
+                .as_str()
+                .to_owned();
+            let input = captures
+                .name("input")
+                .expect("could not get propose 'type'")
+                .as_str()
+                .to_owned();
+            let result = self.ballot.start_voting(&identity, &r#type, &input);
+            let value = match result {
+                Ok(_) => self.ballot.get_proposal().to_owned(),
+                Err(_) => input,
+            };
+            let name = self.get_nomination_name(&r#type);
+            let motion = Motion {
+                text: self
+                    .messages
+                    .format("motion", &[("type", &name), ("value", &value)]),
+                r#type: name,
+                value,
+                proposer: username.to_owned(),
+            };
+            self.announce_voting(&voter, motion, result);
+        }
+        if let Some(captures) = REGEX_CHAT_VOTE.captures(message) {
+            let vote = captures
+                .name("vote")
+                .expect("could not get propose 'type'")
+                .as_str();
+            if let Ok(()) = match vote {
+                "yay" => self.ballot.vote(&voter, true),
+                "nay" => self.ballot.vote(&voter, false),
+                _ => Err(VoteError::Progress),
+            } {
+                self.print_requirements();
+                self.check_vote_result(false);
+            }
+        }
+    }
+
+    /// Whether the message would propose, nominate or vote at this point.
+    fn is_voting_command(&self, message: &str) -> bool {
+        (self.poll.is_polling() && REGEX_CHAT_CHOICE.is_match(message))
+            || (self.ballot.is_voting() && REGEX_CHAT_VOTE.is_match(message))
+            || REGEX_CHAT_RTV.is_match(message)
+            || REGEX_CHAT_NOMINATE.is_match(message)
+            || REGEX_CHAT_PROPOSE.is_match(message)
+    }
+
+    fn rock_the_vote(&mut self, voter: &str, username: &str) {
+        if self.ballot.is_voting() || self.poll.is_polling() {
+            self.say("in_progress", &[]);
+            return;
+        }
+        match self.rtv.add(voter) {
+            Ok(_) => {
+                let voters = self.players.get_voter_count(None);
+                let count = self.rtv.get_count().to_string();
+                let requirement = self.rtv.get_requirement(voters).to_string();
+                self.say(
+                    "rtv",
+                    &[
+                        ("player", username),
+                        ("yays", &count),
+                        ("needed", &requirement),
+                    ],
+                );
+                self.check_rtv();
+            }
+            Err(RtvError::Cooldown(duration)) => {
+                let remaining = format!("{:.0}", duration);
+                self.say("rtv_cooldown", &[("remaining", &remaining)]);
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Starts a map vote once enough players asked for it.
+    fn check_rtv(&mut self) {
+        if self.ballot.is_voting() || self.poll.is_polling() {
+            return;
+        }
+        if !self.rtv.is_reached(self.players.get_voter_count(None)) {
+            return;
+        }
+        let count = self
+            .get_poll_candidates()
+            .map(|(_, candidates)| candidates.len())
+            .unwrap_or(0);
+        // The requests are kept, so the map vote still starts once maps are available.
+        if count < 2 {
+            let keyword = self.poll.get_nomination().to_owned();
+            let name = self.get_nomination_name(&keyword);
+            self.say("rtv_no_candidates", &[("type", &name)]);
+            return;
+        }
+        self.rtv.clear();
+        self.start_poll();
+    }
+
+    fn nominate(&mut self, voter: &str, username: &str, input: &str) {
+        let keyword = self.poll.get_nomination().to_owned();
+        let name = self.get_nomination_name(&keyword);
+        match self.ballot.check_proposal(&keyword, input) {
+            Ok(value) => {
+                self.poll.nominate(voter, &value);
+                self.say(
+                    "nominated",
+                    &[("player", username), ("type", &name), ("value", &value)],
+                );
+            }
+            Err(error) => {
+                let motion = self
+                    .messages
+                    .format("motion", &[("type", &name), ("value", input)]);
+                self.report_vote_error(username, &motion, error);
+            }
+        }
+    }
+
+    fn propose_sanction(&mut self, id: &str, username: &str, query: &str, minutes: Option<u64>) {
+        let target = match self.players.find(query) {
+            Ok(target) => target,
+            Err(PlayerError::NotFound) => {
+                self.say("player_missing", &[("value", query)]);
+                return;
+            }
+            Err(PlayerError::Ambiguous(names)) => {
+                let candidates = names.join(", ");
+                self.say(
+                    "player_ambiguous",
+                    &[("value", query), ("candidates", &candidates)],
+                );
+                return;
+            }
+        };
+        let name = match self.players.get(&target) {
+            Some(player) => strip_colors(player.get_name()),
+            None => target.clone(),
+        };
+        let mut target_ip = None;
+        let (r#type, proposal, text) = match minutes {
+            Some(minutes) => {
+                let max_minutes = self.sanctions.get_max_ban_duration().as_secs() / 60;
+                if minutes == 0 || minutes > max_minutes {
+                    let max_minutes = max_minutes.to_string();
+                    self.say("ban_duration", &[("minutes", &max_minutes)]);
+                    return;
+                }
+                target_ip = self.find_ip(&target);
+                if target_ip.is_none() {
+                    self.say("ban_no_address", &[("player", &name)]);
+                    return;
+                }
+                let minutes = minutes.to_string();
+                (
+                    TEMPBAN,
+                    format!("{} {}", target, minutes),
+                    self.messages.format(
+                        "motion_tempban",
+                        &[("player", &name), ("minutes", &minutes)],
+                    ),
+                )
+            }
+            None => (
+                KICK,
+                target.clone(),
+                self.messages.format("motion_kick", &[("player", &name)]),
+            ),
+        };
+        let identity = self.players.get_identity(id);
+        let voter = self.players.get_voter(id);
+        let result = match target == id {
+            true => Err(VoteError::Target),
+            false => {
+                let target_voter = self.players.get_voter(&target);
+                self.ballot
+                    .start_targeted_voting(&identity, r#type, &proposal, &target_voter)
+            }
+        };
+        if result.is_ok() {
+            self.target_ip = target_ip;
+        }
+        let motion = Motion {
+            text,
+            r#type: self.get_nomination_name(r#type),
+            value: name,
+            proposer: username.to_owned(),
+        };
+        self.announce_voting(&voter, motion, result);
+    }
+
+    /// The address a tempban would ban, asked from the server when the log did not
+    /// give it.
+    fn find_ip(&mut self, id: &str) -> Option<String> {
+        if let Some(ip) = self.players.get(id).and_then(Player::get_ip) {
+            return Some(ip.to_owned());
+        }
+        let status = match self.console.status() {
+            Ok(status) => status,
+            Err(error) => {
+                eprintln!("could not get the server status: {}", error);
+                return None;
+            }
+        };
+        let ip = status
+            .players
+            .into_iter()
+            .find(|player| player.slot == id)?
+            .get_ip()?;
+        self.players.set_ip(id, &ip);
+        Some(ip)
+    }
+
+    /// Announces the motion that opened, in which the proposer's `voter` votes yes.
+    fn announce_voting(&mut self, voter: &str, motion: Motion, result: Result<(), VoteError>) {
+        match result {
+            Ok(_) => {
+                self.save();
+                self.refresh_player_count();
+                self.say("vote_started", &[("motion", &motion.text)]);
+                self.motion = motion;
+                self.say("vote_yay", &[]);
+                self.say("vote_nay", &[]);
+                let (yay, nay) = self.ballot.get_requirements();
+                self.say("yays_needed", &[("needed", &yay.to_string())]);
+                self.say("nays_needed", &[("needed", &nay.to_string())]);
+                self.ballot.vote(voter, true).ok();
+                self.check_vote_result(false);
+            }
+            Err(error) => self.report_vote_error(&motion.proposer, &motion.text, error),
+        }
+    }
+
+    fn report_vote_error(&mut self, username: &str, motion: &str, error: VoteError) {
+        match error {
+            VoteError::Cooldown(duration) => {
+                let remaining = format!("{:.2}", duration);
+                self.say(
+                    "cooldown",
+                    &[("player", username), ("remaining", &remaining)],
+                );
+            }
+            VoteError::Progress => {
+                self.say("in_progress", &[]);
+            }
+            VoteError::Nomination(suggestions) => {
+                let candidates = suggestions.join(", ");
+                let message = match suggestions.is_empty() {
+                    true => "not_listed",
+                    false => "not_listed_suggestions",
                 };
+                self.say(message, &[("motion", motion), ("candidates", &candidates)]);
+            }
+            VoteError::Ambiguous(candidates) => {
+                let candidates = candidates.join(", ");
+                self.say(
+                    "ambiguous",
+                    &[("motion", motion), ("candidates", &candidates)],
+                );
+            }
+            VoteError::Target => {
+                self.say("self_target", &[]);
+            }
+            VoteError::Recent(maps_left) => {
+                let remaining = maps_left.to_string();
+                self.say("recent", &[("motion", motion), ("remaining", &remaining)]);
             }
-            None => {}
+            _ => {}
+        }
+    }
+
+    fn get_nomination_name(&self, r#type: &str) -> String {
+        match self.ballot.get_nomination(r#type) {
+            Some(nomination) => nomination.get_name().to_owned(),
+            None => r#type.to_owned(),
         }
     }
 
     fn print_requirements(&mut self) {
         let (yay, nay) = self.ballot.get_votes();
         let (yay_needed, nay_needed) = self.ballot.get_requirements();
-        self.console
-            .svsay(format!("{}/{} yay - {}/{} nay", yay, yay_needed, nay, nay_needed).as_bytes());
+        self.say(
+            "requirements",
+            &[
+                ("yays", &yay.to_string()),
+                ("needed", &yay_needed.to_string()),
+                ("nays", &nay.to_string()),
+                ("nays_needed", &nay_needed.to_string()),
+            ],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use console::MockConsole;
+    use nomination::Nomination;
+
+    const STATUS: &str = "map: mb2_dotf\n\
+        num score ping name            address                                 rate\n\
+        --- ----- ---- --------------- --------------------------------------- -----\n\
+        \x20 0     5   36 Alice           1.1.1.1:29071                           25000\n\
+        \x20 1     0    0 Bot             bot                                     25000\n";
+
+    fn create_test_system() -> System<MockConsole> {
+        let maps = ["mb2_dotf", "mb2_kamino"]
+            .iter()
+            .map(|map| (*map).to_owned())
+            .collect();
+        let mut nominations = HashMap::new();
+        nominations.insert(
+            "map".to_owned(),
+            Nomination::new("Map", "map", maps, "map {value}", true),
+        );
+        let ballot = Ballot::new(
+            Duration::from_secs(30),
+            Duration::from_secs(30),
+            0.6,
+            nominations,
+            3,
+        );
+        let poll = Poll::new("map", Duration::from_secs(30), Method::Plurality, 5, false);
+        let rtv = RockTheVote::new(0.5, Duration::from_secs(180));
+        let sanctions = Sanctions::new(
+            "clientkick {id}",
+            "addip {ip}",
+            "removeip {ip}",
+            Duration::from_secs(3600),
+        );
+        let store = ServerStore::new(Rc::new(RefCell::new(Store::in_memory(10))), "");
+        let messages = Messages::new(&HashMap::new()).expect("could not get messages");
+        System::new(
+            MockConsole::new(),
+            ballot,
+            poll,
+            rtv,
+            Players::new(true),
+            sanctions,
+            store,
+            messages,
+        )
+    }
+
+    fn play(system: &mut System<MockConsole>, lines: &[&str]) {
+        for line in lines {
+            match line.parse() {
+                Ok(event) => system.handle_event(event),
+                Err(error) => panic!("could not parse '{}': {:?}", line, error),
+            }
+        }
+    }
+
+    /// Puts four players from different addresses in the game, slot 3 being the troll.
+    fn join_players(system: &mut System<MockConsole>) {
+        for (id, name) in ["Alice", "Bob", "Carol", "Troll"].iter().enumerate() {
+            play(
+                system,
+                &[
+                    &format!("  0:01 ClientConnect: {} [10.0.0.{}]", id, id + 1),
+                    &format!("  0:01 ClientUserinfoChanged: {} n\\{}\\t\\1", id, name),
+                    &format!("  0:01 ClientBegin: {}", id),
+                ],
+            );
+        }
+        system.console.take_commands();
+    }
+
+    /// Splits what was sent since the last call into the chat and the other commands.
+    fn take_output(system: &mut System<MockConsole>) -> (Vec<String>, Vec<String>) {
+        system
+            .console
+            .take_commands()
+            .into_iter()
+            .partition(|command| command.starts_with("svsay "))
+    }
+
+    #[test]
+    fn granted_motion_runs_the_command() {
+        let mut system = create_test_system();
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:02 0: say: Alice: \"vote map dotf\"",
+                "  0:03 1: say: Bob: \"yay\"",
+            ],
+        );
+        let (_, commands) = take_output(&mut system);
+        assert!(commands.is_empty());
+        play(&mut system, &["  0:04 2: say: Carol: \"yay\""]);
+        let (said, commands) = take_output(&mut system);
+        assert!(said.contains(&"svsay Yay vote majority, motion granted.".to_owned()));
+        assert_eq!(commands, vec!["map mb2_dotf"]);
+        assert!(!system.ballot.is_voting());
+    }
+
+    #[test]
+    fn result_messages_name_the_motion() {
+        let mut system = create_test_system();
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "denied".to_owned(),
+            "{player} lost {type} {value}: {motion}".to_owned(),
+        );
+        system.messages = Messages::new(&overrides).expect("could not get messages");
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:02 0: say: Alice: \"vote map kam\"",
+                "  0:03 1: say: Bob: \"nay\"",
+            ],
+        );
+        let (said, _) = take_output(&mut system);
+        assert!(said.contains(&"svsay Alice lost Map mb2_kamino: Map 'mb2_kamino'".to_owned()));
+    }
+
+    #[test]
+    fn spectators_have_no_say_when_they_may_not_vote() {
+        let mut system = create_test_system();
+        system.players = Players::new(false);
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:02 ClientUserinfoChanged: 2 n\\Carol\\t\\3",
+                "  0:02 ClientUserinfoChanged: 3 n\\Troll\\t\\3",
+                "  0:03 3: say: Troll: \"vote map kamino\"",
+            ],
+        );
+        assert!(!system.ballot.is_voting());
+        play(
+            &mut system,
+            &[
+                "  0:04 0: say: Alice: \"vote map dotf\"",
+                "  0:05 2: say: Carol: \"yay\"",
+            ],
+        );
+        let (said, commands) = take_output(&mut system);
+        assert!(said.contains(&"svsay User 'Carol' can't vote!".to_owned()));
+        assert!(commands.is_empty());
+        assert_eq!(system.ballot.get_votes(), (1, 0));
+        play(&mut system, &["  0:06 1: say: Bob: \"yay\""]);
+        let (_, commands) = take_output(&mut system);
+        assert_eq!(commands, vec!["map mb2_dotf"]);
+    }
+
+    #[test]
+    fn denied_motion_runs_nothing() {
+        let mut system = create_test_system();
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:02 0: say: Alice: \"vote map kamino\"",
+                "  0:03 1: say: Bob: \"nay\"",
+            ],
+        );
+        let (said, commands) = take_output(&mut system);
+        assert!(said.contains(&"svsay Nay vote majority, motion denied.".to_owned()));
+        assert!(commands.is_empty());
+        assert!(!system.ballot.is_voting());
+    }
+
+    #[test]
+    fn kick_leaves_the_target_out_of_the_vote() {
+        let mut system = create_test_system();
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:02 0: say: Alice: \"vote kick troll\"",
+                "  0:03 3: say: Troll: \"nay\"",
+            ],
+        );
+        let (_, commands) = take_output(&mut system);
+        assert!(commands.is_empty());
+        play(&mut system, &["  0:04 1: say: Bob: \"yay\""]);
+        let (_, commands) = take_output(&mut system);
+        assert_eq!(commands, vec!["clientkick 3"]);
+    }
+
+    #[test]
+    fn kick_is_dismissed_when_the_target_leaves() {
+        let mut system = create_test_system();
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:02 0: say: Alice: \"vote kick troll\"",
+                "  0:03 ClientDisconnect: 3",
+            ],
+        );
+        let (said, commands) = take_output(&mut system);
+        assert!(said.contains(&"svsay Player has left, motion dismissed.".to_owned()));
+        assert!(commands.is_empty());
+        assert!(!system.ballot.is_voting());
+    }
+
+    #[test]
+    fn tempban_bans_and_kicks_the_target() {
+        let mut system = create_test_system();
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:02 0: say: Alice: \"vote tempban troll 5\"",
+                "  0:03 1: say: Bob: \"yay\"",
+            ],
+        );
+        let (_, commands) = take_output(&mut system);
+        assert_eq!(commands, vec!["addip 10.0.0.4", "clientkick 3"]);
+        assert_eq!(
+            system.take_bans(),
+            vec![("10.0.0.4".to_owned(), Duration::from_secs(300))]
+        );
+        assert!(system.sanctions.get_bans().contains_key("10.0.0.4"));
+    }
+
+    #[test]
+    fn tempban_goes_on_after_the_target_leaves() {
+        let mut system = create_test_system();
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:02 0: say: Alice: \"vote tempban troll 5\"",
+                "  0:03 ClientDisconnect: 3",
+                "  0:04 ClientConnect: 3 [10.0.0.9]",
+                "  0:04 ClientBegin: 3",
+            ],
+        );
+        assert!(system.ballot.is_voting());
+        // The newcomer in the slot is a voter like any other.
+        play(
+            &mut system,
+            &[
+                "  0:05 1: say: Bob: \"yay\"",
+                "  0:05 2: say: Carol: \"yay\"",
+            ],
+        );
+        let (_, commands) = take_output(&mut system);
+        assert_eq!(commands, vec!["addip 10.0.0.4"]);
+    }
+
+    #[test]
+    fn tempban_asks_the_server_for_a_missing_address() {
+        let mut system = create_test_system();
+        join_players(&mut system);
+        system.console.respond(
+            "status",
+            "map: mb2_dotf\n\
+             num score ping name            address                                 rate\n\
+             --- ----- ---- --------------- --------------------------------------- -----\n\
+             \x20 4     0   50 Sith            10.0.0.5:29070                          25000\n",
+        );
+        play(
+            &mut system,
+            &[
+                "  0:01 ClientConnect: 4",
+                "  0:01 ClientUserinfoChanged: 4 n\\Sith\\t\\2",
+                "  0:01 ClientBegin: 4",
+                "  0:02 0: say: Alice: \"vote tempban sith 5\"",
+                "  0:03 1: say: Bob: \"yay\"",
+                "  0:04 2: say: Carol: \"yay\"",
+            ],
+        );
+        let (_, commands) = take_output(&mut system);
+        assert_eq!(commands, vec!["status", "addip 10.0.0.5", "clientkick 4"]);
+    }
+
+    #[test]
+    fn tempban_is_refused_without_an_address() {
+        let mut system = create_test_system();
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:01 ClientConnect: 4",
+                "  0:01 ClientUserinfoChanged: 4 n\\Sith\\t\\2",
+                "  0:01 ClientBegin: 4",
+                "  0:02 0: say: Alice: \"vote tempban sith 5\"",
+            ],
+        );
+        let (said, commands) = take_output(&mut system);
+        assert!(said
+            .contains(&"svsay The address of 'Sith' is unknown, they can't be banned!".to_owned()));
+        assert_eq!(commands, vec!["status"]);
+        assert!(!system.ballot.is_voting());
+    }
+
+    #[test]
+    fn tempban_kicks_the_target_back_in_another_slot() {
+        let mut system = create_test_system();
+        join_players(&mut system);
+        play(
+            &mut system,
+            &[
+                "  0:02 0: say: Alice: \"vote tempban troll 5\"",
+                "  0:03 ClientDisconnect: 3",
+                "  0:04 ClientConnect: 5 [10.0.0.4]",
+                "  0:04 ClientBegin: 5",
+                "  0:05 1: say: Bob: \"yay\"",
+                "  0:05 2: say: Carol: \"yay\"",
+            ],
+        );
+        let (_, commands) = take_output(&mut system);
+        assert_eq!(commands, vec!["addip 10.0.0.4", "clientkick 5"]);
+    }
+
+    #[test]
+    fn players_are_loaded_from_status() {
+        let mut system = create_test_system();
+        system.console.respond("status", STATUS);
+        system.load_players();
+        assert_eq!(system.get_console().get_commands(), ["status"]);
+        assert_eq!(system.players.get_voter_count(None), 1);
+        assert_eq!(system.ballot.get_maps_until_available("mb2_dotf"), Some(3));
     }
 }
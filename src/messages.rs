@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+const DEFAULT_COLOR: &str = "^7";
+
+/// The built-in English messages, with the placeholders each of them is given.
+/// A template emptied in the catalog is not said.
+const DEFAULTS: [(&str, &str, &[&str]); 35] = [
+    ("motion", "{type} '{value}'", &["type", "value"]),
+    ("motion_kick", "Kick '{player}'", &["player"]),
+    (
+        "motion_tempban",
+        "Tempban '{player}' for {minutes} minute(s)",
+        &["player", "minutes"],
+    ),
+    ("vote_started", "{motion} is nominated!", &["motion"]),
+    ("vote_yay", "type 'yay' to vote yes", &[]),
+    ("vote_nay", "type 'nay' to vote no", &[]),
+    (
+        "yays_needed",
+        "{needed} yay vote(s) needed for motion",
+        &["needed"],
+    ),
+    (
+        "nays_needed",
+        "{needed} nay vote(s) needed to deny",
+        &["needed"],
+    ),
+    (
+        "requirements",
+        "{yays}/{needed} yay - {nays}/{nays_needed} nay",
+        &["yays", "needed", "nays", "nays_needed"],
+    ),
+    (
+        "granted",
+        "Yay vote majority, motion granted.",
+        &["motion", "type", "value", "player"],
+    ),
+    (
+        "denied",
+        "Nay vote majority, motion denied.",
+        &["motion", "type", "value", "player"],
+    ),
+    (
+        "deadlock",
+        "Voting deadlock, motion denied.",
+        &["motion", "type", "value", "player"],
+    ),
+    ("player_left", "Player has left, motion dismissed.", &[]),
+    ("in_progress", "Voting is currently in progress!", &[]),
+    ("not_voter", "User '{player}' can't vote!", &["player"]),
+    (
+        "cooldown",
+        "User '{player}' is in cooldown for {remaining} second!",
+        &["player", "remaining"],
+    ),
+    (
+        "not_listed",
+        "{motion} is not on the list!",
+        &["motion", "candidates"],
+    ),
+    (
+        "not_listed_suggestions",
+        "{motion} is not on the list! Did you mean: {candidates}?",
+        &["motion", "candidates"],
+    ),
+    (
+        "ambiguous",
+        "{motion} matches several: {candidates}",
+        &["motion", "candidates"],
+    ),
+    (
+        "self_target",
+        "You can't start a motion against yourself!",
+        &[],
+    ),
+    (
+        "recent",
+        "{motion} was played recently, available again in {remaining} map(s)!",
+        &["motion", "remaining"],
+    ),
+    ("player_missing", "No player matches '{value}'!", &["value"]),
+    (
+        "player_ambiguous",
+        "Multiple players match '{value}': {candidates}",
+        &["value", "candidates"],
+    ),
+    (
+        "ban_no_address",
+        "The address of '{player}' is unknown, they can't be banned!",
+        &["player"],
+    ),
+    (
+        "ban_duration",
+        "Ban duration must be 1 to {minutes} minute(s)!",
+        &["minutes"],
+    ),
+    (
+        "nominated",
+        "User '{player}' nominated {type} '{value}'!",
+        &["player", "type", "value"],
+    ),
+    (
+        "rtv",
+        "User '{player}' wants to rock the vote! ({yays}/{needed})",
+        &["player", "yays", "needed"],
+    ),
+    (
+        "rtv_cooldown",
+        "Rock the vote is available in {remaining} second(s)!",
+        &["remaining"],
+    ),
+    (
+        "rtv_no_candidates",
+        "Not enough {type} candidates to vote on!",
+        &["type"],
+    ),
+    (
+        "poll_plurality",
+        "{type} vote! type the number of your choice:",
+        &["type"],
+    ),
+    (
+        "poll_instant_runoff",
+        "{type} vote! type the numbers of your choices, best first:",
+        &["type"],
+    ),
+    ("poll_candidate", "{number}: {value}", &["number", "value"]),
+    ("poll_choice", "Choose between 1 and {needed}!", &["needed"]),
+    (
+        "poll_won",
+        "{type} '{value}' won the vote!",
+        &["type", "value"],
+    ),
+    ("poll_empty", "Nobody voted, motion dismissed.", &[]),
+];
+
+lazy_static! {
+    static ref REGEX_PLACEHOLDER: Regex = Regex::new(r#"\{(?P<name>[a-z_]+)\}"#).unwrap();
+    static ref REGEX_COLOR: Regex = Regex::new(r#"\^[0-9]"#).unwrap();
+}
+
+#[derive(Debug)]
+pub enum MessageError {
+    Unknown(String),
+    Placeholder(String, String),
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageError::Unknown(name) => write!(f, "there is no message named '{}'", name),
+            MessageError::Placeholder(name, placeholder) => write!(
+                f,
+                "message '{}' is never given a '{{{}}}' placeholder",
+                name, placeholder
+            ),
+        }
+    }
+}
+
+/// The templates of everything said in the chat. `{name}` placeholders are
+/// replaced by the values given when formatting, and the `^1` to `^7` color
+/// codes are passed on to the game.
+pub struct Messages {
+    templates: HashMap<String, String>,
+}
+
+impl Messages {
+    /// Takes the built-in messages, replaced by the given ones. A replacement
+    /// may only use the placeholders its message is given.
+    pub fn new(overrides: &HashMap<String, String>) -> Result<Messages, MessageError> {
+        let mut templates: HashMap<String, String> = DEFAULTS
+            .iter()
+            .map(|(name, template, _)| (name.to_string(), template.to_string()))
+            .collect();
+        for (name, template) in overrides {
+            let placeholders = match DEFAULTS.iter().find(|(default, _, _)| default == name) {
+                Some((_, _, placeholders)) => placeholders,
+                None => return Err(MessageError::Unknown(name.to_owned())),
+            };
+            if let Some(captures) = REGEX_PLACEHOLDER
+                .captures_iter(template)
+                .find(|captures| !placeholders.contains(&&captures["name"]))
+            {
+                let placeholder = captures["name"].to_owned();
+                return Err(MessageError::Placeholder(name.to_owned(), placeholder));
+            }
+            templates.insert(name.to_owned(), template.to_owned());
+        }
+        Ok(Messages { templates })
+    }
+
+    /// Fills in the template. When a value brings its own colors, like a player
+    /// name, the color of the template is restored after it.
+    pub fn format(&self, name: &str, values: &[(&str, &str)]) -> String {
+        let template = match self.templates.get(name) {
+            Some(template) => template,
+            None => return String::new(),
+        };
+        let mut message = String::new();
+        let mut end = 0;
+        for captures in REGEX_PLACEHOLDER.captures_iter(template) {
+            let placeholder = captures.get(0).expect("could not get placeholder");
+            let key = captures
+                .name("name")
+                .expect("could not get placeholder 'name'")
+                .as_str();
+            let value = match values.iter().find(|(name, _)| *name == key) {
+                Some((_, value)) => value,
+                None => continue,
+            };
+            message.push_str(&template[end..placeholder.start()]);
+            message.push_str(value);
+            if REGEX_COLOR.is_match(value) {
+                let color = REGEX_COLOR
+                    .find_iter(&template[..placeholder.start()])
+                    .last()
+                    .map_or(DEFAULT_COLOR, |color| color.as_str());
+                message.push_str(color);
+            }
+            end = placeholder.end();
+        }
+        message.push_str(&template[end..]);
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with(name: &str, template: &str) -> Result<Messages, MessageError> {
+        let mut overrides = HashMap::new();
+        overrides.insert(name.to_owned(), template.to_owned());
+        Messages::new(&overrides)
+    }
+
+    #[test]
+    fn defaults_only_use_their_placeholders() {
+        for (name, template, _) in DEFAULTS.iter() {
+            assert!(with(name, template).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn overrides_are_checked() {
+        assert!(with("granted", "^2{motion} by {player} granted").is_ok());
+        match with("vote_yay", "{player}, type yay") {
+            Err(MessageError::Placeholder(name, placeholder)) => {
+                assert_eq!(
+                    (name.as_str(), placeholder.as_str()),
+                    ("vote_yay", "player")
+                )
+            }
+            _ => panic!("'vote_yay' is never given a player"),
+        }
+        match with("granted!", "Granted") {
+            Err(MessageError::Unknown(name)) => assert_eq!(name, "granted!"),
+            _ => panic!("'granted!' is not a message"),
+        }
+    }
+
+    #[test]
+    fn color_is_restored_after_a_value() {
+        let messages = with("cooldown", "^3Wait {player}, {remaining}s")
+            .ok()
+            .unwrap();
+        assert_eq!(
+            messages.format("cooldown", &[("player", "^1Vader"), ("remaining", "5")]),
+            "^3Wait ^1Vader^3, 5s"
+        );
+    }
+}
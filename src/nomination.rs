@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+
+use crate::util::get_edit_distance;
+
+const VALUE_PLACEHOLDER: &str = "{value}";
+const CANDIDATE_LIMIT: usize = 5;
+const SUGGESTION_LIMIT: usize = 3;
+
+/// What a player typed, resolved against the values of a nomination category.
+pub enum Match {
+    Found(String),
+    Ambiguous(Vec<String>),
+    Missing(Vec<String>),
+}
+
+pub struct Nomination {
+    name: String,
+    keyword: String,
+    values: HashSet<String>,
+    command: String,
+    rotation: bool,
+}
+
+impl Nomination {
+    pub fn new(
+        name: &str,
+        keyword: &str,
+        values: HashSet<String>,
+        command: &str,
+        rotation: bool,
+    ) -> Nomination {
+        Nomination {
+            name: name.to_owned(),
+            keyword: keyword.to_owned(),
+            values,
+            command: command.to_owned(),
+            rotation,
+        }
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn get_keyword(&self) -> &str {
+        &self.keyword
+    }
+    pub fn get_values(&self) -> &HashSet<String> {
+        &self.values
+    }
+    /// Resolves a partial or misspelled value. Exact and case-insensitive matches
+    /// come first, then names that are the input behind a `prefix_`, then names
+    /// where it starts a `_` separated word and finally names merely containing
+    /// it. Without any of those, a close enough spelling is accepted when it is
+    /// the only one.
+    pub fn find(&self, input: &str) -> Match {
+        if self.values.contains(input) {
+            return Match::Found(input.to_owned());
+        }
+        let query = input.to_lowercase();
+        if query.is_empty() {
+            return Match::Missing(Vec::new());
+        }
+        let mut matches: Vec<(usize, &String)> = self
+            .values
+            .iter()
+            .filter_map(|value| {
+                get_match_rank(&value.to_lowercase(), &query).map(|rank| (rank, value))
+            })
+            .collect();
+        if let Some(best) = matches.iter().map(|(rank, _)| *rank).min() {
+            matches.retain(|(rank, _)| *rank == best);
+            let mut candidates: Vec<String> = matches
+                .into_iter()
+                .map(|(_, value)| value.clone())
+                .collect();
+            if candidates.len() == 1 {
+                return Match::Found(candidates.remove(0));
+            }
+            candidates.sort();
+            candidates.truncate(CANDIDATE_LIMIT);
+            return Match::Ambiguous(candidates);
+        }
+        let length = query.chars().count();
+        let mut distances: Vec<(usize, &String)> = self
+            .values
+            .iter()
+            .map(|value| (get_name_distance(&value.to_lowercase(), &query), value))
+            .filter(|(distance, _)| *distance < length)
+            .collect();
+        distances.sort();
+        let closest: Vec<String> = distances
+            .iter()
+            .map(|(_, value)| (*value).clone())
+            .collect();
+        match distances.as_slice() {
+            [(distance, value)] if *distance <= length / 4 => Match::Found((*value).clone()),
+            [(distance, value), (next, _), ..] if *distance <= length / 4 && distance < next => {
+                Match::Found((*value).clone())
+            }
+            _ => Match::Missing(closest.into_iter().take(SUGGESTION_LIMIT).collect()),
+        }
+    }
+    /// Whether the values are maps that fall under the recently played rule.
+    pub fn is_rotation(&self) -> bool {
+        self.rotation
+    }
+    /// Fills the `{value}` placeholder of the rcon command template.
+    pub fn get_command(&self, value: &str) -> String {
+        self.command.replace(VALUE_PLACEHOLDER, value)
+    }
+}
+
+/// Lower is better, `None` when the value does not contain the query at all.
+fn get_match_rank(value: &str, query: &str) -> Option<usize> {
+    let position = value.find(query)?;
+    let at_boundary = position == 0 || value[..position].ends_with('_');
+    if value == query {
+        Some(0)
+    } else if strip_prefix(value) == query {
+        Some(1)
+    } else if at_boundary {
+        Some(2)
+    } else {
+        Some(3)
+    }
+}
+
+/// Compares against both the full name and the name without its `prefix_`.
+fn get_name_distance(value: &str, query: &str) -> usize {
+    get_edit_distance(value, query).min(get_edit_distance(strip_prefix(value), query))
+}
+
+fn strip_prefix(value: &str) -> &str {
+    match value.find('_') {
+        Some(position) => &value[position + 1..],
+        None => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn maps() -> Nomination {
+        let values = [
+            "mb2_dotf",
+            "mb2_deathstar",
+            "mb2_deathstar_ii",
+            "mb2_mustafar",
+            "mb2_kamino",
+        ]
+        .iter()
+        .map(|map| (*map).to_owned())
+        .collect();
+        Nomination::new("Map", "map", values, "map {value}", true)
+    }
+
+    fn found(result: Match) -> Option<String> {
+        match result {
+            Match::Found(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn exact_and_case_insensitive() {
+        assert_eq!(found(maps().find("mb2_dotf")), Some("mb2_dotf".to_owned()));
+        assert_eq!(found(maps().find("MB2_Dotf")), Some("mb2_dotf".to_owned()));
+    }
+
+    #[test]
+    fn name_without_prefix() {
+        assert_eq!(found(maps().find("dotf")), Some("mb2_dotf".to_owned()));
+        assert_eq!(
+            found(maps().find("deathstar")),
+            Some("mb2_deathstar".to_owned())
+        );
+    }
+
+    #[test]
+    fn part_of_a_single_name() {
+        assert_eq!(found(maps().find("kam")), Some("mb2_kamino".to_owned()));
+        assert_eq!(found(maps().find("mino")), Some("mb2_kamino".to_owned()));
+    }
+
+    #[test]
+    fn several_names_are_ambiguous() {
+        match maps().find("death") {
+            Match::Ambiguous(candidates) => {
+                assert_eq!(candidates, vec!["mb2_deathstar", "mb2_deathstar_ii"])
+            }
+            _ => panic!("'death' should be ambiguous"),
+        }
+    }
+
+    #[test]
+    fn close_spelling() {
+        assert_eq!(
+            found(maps().find("mustafr")),
+            Some("mb2_mustafar".to_owned())
+        );
+        assert_eq!(found(maps().find("kamno")), Some("mb2_kamino".to_owned()));
+        assert_eq!(found(maps().find("kamnio")), None);
+    }
+
+    #[test]
+    fn missing_names_get_suggestions() {
+        match maps().find("kaminoxyz") {
+            Match::Missing(suggestions) => assert_eq!(suggestions[0], "mb2_kamino"),
+            _ => panic!("'kaminoxyz' should be missing"),
+        }
+        match maps().find("") {
+            Match::Missing(suggestions) => assert!(suggestions.is_empty()),
+            _ => panic!("an empty name should be missing"),
+        }
+    }
+}
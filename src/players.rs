@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use crate::util::{get_userinfo, strip_colors};
+
+pub enum PlayerError {
+    NotFound,
+    Ambiguous(Vec<String>),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Team {
+    Free,
+    Red,
+    Blue,
+    Spectator,
+}
+
+impl Team {
+    fn from_userinfo(value: &str) -> Team {
+        match value {
+            "1" => Team::Red,
+            "2" => Team::Blue,
+            "3" => Team::Spectator,
+            _ => Team::Free,
+        }
+    }
+}
+
+pub struct Player {
+    name: String,
+    ip: Option<String>,
+    guid: Option<String>,
+    team: Team,
+    bot: bool,
+    begun: bool,
+}
+
+impl Player {
+    fn new(ip: Option<String>, guid: Option<String>) -> Player {
+        Player {
+            name: String::new(),
+            ip,
+            guid,
+            team: Team::Spectator,
+            bot: false,
+            begun: false,
+        }
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn get_ip(&self) -> Option<&str> {
+        self.ip.as_deref()
+    }
+    pub fn get_guid(&self) -> Option<&str> {
+        self.guid.as_deref()
+    }
+    /// Prefers the client guid over the IP address, both of which outlive the client slot.
+    pub fn get_identity(&self) -> Option<&str> {
+        self.guid.as_deref().or_else(|| self.get_ip())
+    }
+}
+
+pub struct Players {
+    players: HashMap<String, Player>,
+    spectators_vote: bool,
+}
+
+impl Players {
+    pub fn new(spectators_vote: bool) -> Players {
+        Players {
+            players: HashMap::new(),
+            spectators_vote,
+        }
+    }
+    pub fn connect(&mut self, id: &str, ip: Option<String>, guid: Option<String>) {
+        self.players.insert(id.to_owned(), Player::new(ip, guid));
+    }
+    /// Adds a client that was already on the server, whose team is not known.
+    pub fn add(&mut self, id: &str, name: &str, ip: Option<String>, bot: bool, begun: bool) {
+        let mut player = Player::new(ip, None);
+        player.name = name.to_owned();
+        player.team = Team::Free;
+        player.bot = bot;
+        player.begun = begun;
+        self.players.insert(id.to_owned(), player);
+    }
+    /// Fills in an address the log did not give, from the server status.
+    pub fn set_ip(&mut self, id: &str, ip: &str) {
+        if let Some(player) = self.players.get_mut(id) {
+            player.ip = Some(ip.to_owned());
+        }
+    }
+    pub fn begin(&mut self, id: &str) {
+        self.players
+            .entry(id.to_owned())
+            .or_insert_with(|| Player::new(None, None))
+            .begun = true;
+    }
+    pub fn disconnect(&mut self, id: &str) {
+        self.players.remove(id);
+    }
+    pub fn update(&mut self, id: &str, userinfo: &str) {
+        let userinfo = get_userinfo(userinfo);
+        let player = self
+            .players
+            .entry(id.to_owned())
+            .or_insert_with(|| Player::new(None, None));
+        if let Some(name) = userinfo.get("n") {
+            player.name = name.to_owned();
+        }
+        if let Some(team) = userinfo.get("t") {
+            player.team = Team::from_userinfo(team);
+        }
+        player.bot = userinfo.contains_key("skill");
+    }
+    /// Counts the human clients that have entered the game, leaving spectators
+    /// out unless they are allowed to vote, and the `excluded` voter.
+    pub fn get_voter_count(&self, excluded: Option<&str>) -> i8 {
+        self.players
+            .iter()
+            .filter(|(_, player)| self.may_vote(player))
+            .filter(|(id, _)| excluded != Some(self.get_voter(id).as_str()))
+            .count() as i8
+    }
+    /// Whether the client is one of the players counted by `get_voter_count`.
+    pub fn is_voter(&self, id: &str) -> bool {
+        self.players
+            .get(id)
+            .is_some_and(|player| self.may_vote(player))
+    }
+    fn may_vote(&self, player: &Player) -> bool {
+        player.begun && !player.bot && (self.spectators_vote || player.team != Team::Spectator)
+    }
+    /// Lists the slots of the clients connected from `ip`, in slot order.
+    pub fn find_by_ip(&self, ip: &str) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .players
+            .iter()
+            .filter(|(_, player)| player.get_ip() == Some(ip))
+            .map(|(id, _)| id.to_owned())
+            .collect();
+        ids.sort_by_key(|id| id.parse::<u8>().unwrap_or(u8::MAX));
+        ids
+    }
+    pub fn get(&self, id: &str) -> Option<&Player> {
+        self.players.get(id)
+    }
+    /// Maps a client slot to the identity that cooldowns and bans are recorded under,
+    /// falling back to the slot itself for clients without a guid or IP address.
+    pub fn get_identity(&self, id: &str) -> String {
+        match self
+            .players
+            .get(id)
+            .and_then(|player| player.get_identity())
+        {
+            Some(identity) => identity.to_owned(),
+            None => format!("#{}", id),
+        }
+    }
+    /// Maps a client slot to the key its votes are counted under: the guid, or the
+    /// slot itself. Players sharing an IP address behind a NAT still vote apart.
+    pub fn get_voter(&self, id: &str) -> String {
+        match self.players.get(id).and_then(Player::get_guid) {
+            Some(guid) => guid.to_owned(),
+            None => format!("#{}", id),
+        }
+    }
+    /// Resolves a client slot or a case-insensitive part of a player name to a client slot.
+    pub fn find(&self, query: &str) -> Result<String, PlayerError> {
+        if self.players.contains_key(query) {
+            return Ok(query.to_owned());
+        }
+        let query = query.to_lowercase();
+        if let Some((id, _)) = self
+            .players
+            .iter()
+            .find(|(_, player)| strip_colors(&player.name).to_lowercase() == query)
+        {
+            return Ok(id.to_owned());
+        }
+        let mut matches: Vec<(&String, &Player)> = self
+            .players
+            .iter()
+            .filter(|(_, player)| strip_colors(&player.name).to_lowercase().contains(&query))
+            .collect();
+        match matches.len() {
+            0 => Err(PlayerError::NotFound),
+            1 => Ok(matches.remove(0).0.to_owned()),
+            _ => Err(PlayerError::Ambiguous(
+                matches
+                    .iter()
+                    .map(|(_, player)| strip_colors(&player.name))
+                    .collect(),
+            )),
+        }
+    }
+}
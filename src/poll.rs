@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::cooldown::Cooldown;
+
+pub enum PollError {
+    Progress,
+    Choice,
+    Cooldown(f32),
+}
+
+#[derive(Clone, Copy)]
+pub enum Method {
+    Plurality,
+    InstantRunoff,
+}
+
+impl Method {
+    pub fn from_name(name: &str) -> Option<Method> {
+        match name {
+            "plurality" => Some(Method::Plurality),
+            "instant-runoff" => Some(Method::InstantRunoff),
+            _ => None,
+        }
+    }
+}
+
+/// A vote between several candidates where each player answers with the
+/// number of a candidate, or several numbers ranked by preference.
+pub struct Poll {
+    nomination: String,
+    start_on_shutdown: bool,
+    polling: bool,
+    polling_duration: Cooldown,
+    method: Method,
+    size: usize,
+    candidates: Vec<String>,
+    player_choices: HashMap<String, Vec<usize>>,
+    player_nomination: HashMap<String, String>,
+}
+
+impl Poll {
+    pub fn new(
+        nomination: &str,
+        polling_duration: Duration,
+        method: Method,
+        size: usize,
+        start_on_shutdown: bool,
+    ) -> Poll {
+        Poll {
+            nomination: nomination.to_owned(),
+            start_on_shutdown,
+            polling: false,
+            polling_duration: Cooldown::new(polling_duration),
+            method,
+            size,
+            candidates: Vec::new(),
+            player_choices: HashMap::new(),
+            player_nomination: HashMap::new(),
+        }
+    }
+    /// The keyword of the nomination category the candidates are drawn from.
+    pub fn get_nomination(&self) -> &str {
+        &self.nomination
+    }
+    pub fn is_started_on_shutdown(&self) -> bool {
+        self.start_on_shutdown
+    }
+    pub fn is_polling(&self) -> bool {
+        self.polling
+    }
+    pub fn get_method(&self) -> Method {
+        self.method
+    }
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+    /// Records the candidate a player wants on the next poll, replacing their previous one.
+    pub fn nominate(&mut self, id: &str, candidate: &str) {
+        self.player_nomination
+            .insert(id.to_owned(), candidate.to_owned());
+    }
+    pub fn unnominate(&mut self, id: &str) {
+        self.player_nomination.remove(id);
+    }
+    pub fn clear_nominations(&mut self) {
+        self.player_nomination.clear();
+    }
+    /// Lists the nominated candidates, the most popular first.
+    pub fn get_nominations(&self) -> Vec<String> {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for candidate in self.player_nomination.values() {
+            match counts.iter_mut().find(|(name, _)| name == candidate) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((candidate.to_owned(), 1)),
+            }
+        }
+        counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+        counts.into_iter().map(|(name, _)| name).collect()
+    }
+    pub fn start_polling(&mut self, candidates: Vec<String>) -> Result<(), PollError> {
+        if self.polling {
+            return Err(PollError::Progress);
+        }
+        if candidates.len() < 2 {
+            return Err(PollError::Choice);
+        }
+        self.polling = true;
+        self.polling_duration.put_in_cooldown();
+        self.candidates = candidates;
+        self.player_nomination.clear();
+        Ok(())
+    }
+    pub fn stop_polling(&mut self) -> Result<(), PollError> {
+        if !self.polling {
+            return Err(PollError::Progress);
+        }
+        self.polling = false;
+        self.polling_duration.clear_cooldown();
+        self.candidates.clear();
+        self.player_choices.clear();
+        Ok(())
+    }
+    pub fn get_candidates(&self) -> &[String] {
+        &self.candidates
+    }
+    /// Takes the 1-based candidate numbers a player typed, in order of preference.
+    pub fn vote(&mut self, id: &str, choices: &[usize]) -> Result<(), PollError> {
+        if !self.polling {
+            return Err(PollError::Progress);
+        }
+        let mut ranking: Vec<usize> = Vec::new();
+        for choice in choices {
+            if *choice == 0 || *choice > self.candidates.len() {
+                return Err(PollError::Choice);
+            }
+            if !ranking.contains(&(choice - 1)) {
+                ranking.push(choice - 1);
+            }
+        }
+        if ranking.is_empty() {
+            return Err(PollError::Choice);
+        }
+        if let Method::Plurality = self.method {
+            ranking.truncate(1);
+        }
+        self.player_choices.insert(id.to_owned(), ranking);
+        Ok(())
+    }
+    pub fn unvote(&mut self, id: &str) {
+        self.player_choices.remove(id);
+    }
+    /// Picks the winner once the poll is over, `None` when nobody voted.
+    pub fn get_result(&self) -> Result<Option<String>, PollError> {
+        if !self.polling {
+            return Err(PollError::Progress);
+        }
+        if self.polling_duration.is_in_cooldown() {
+            return Err(PollError::Cooldown(
+                self.polling_duration.get_remaining_time(),
+            ));
+        }
+        let winner = match self.method {
+            Method::Plurality => self.get_plurality_winner(),
+            Method::InstantRunoff => self.get_instant_runoff_winner(),
+        };
+        Ok(winner.map(|index| self.candidates[index].clone()))
+    }
+    /// Counts each ballot for its most preferred candidate that is still running.
+    fn count(&self, running: &[bool]) -> Vec<usize> {
+        let mut tally = vec![0; self.candidates.len()];
+        for ranking in self.player_choices.values() {
+            if let Some(choice) = ranking.iter().find(|choice| running[**choice]) {
+                tally[*choice] += 1;
+            }
+        }
+        tally
+    }
+    /// Ties go to the candidate listed first.
+    fn get_plurality_winner(&self) -> Option<usize> {
+        let tally = self.count(&vec![true; self.candidates.len()]);
+        let most = *tally.iter().max()?;
+        if most == 0 {
+            return None;
+        }
+        tally.iter().position(|votes| *votes == most)
+    }
+    /// Drops the weakest candidate until one holds a majority of the remaining
+    /// ballots. Ties for last place eliminate the candidate listed last.
+    fn get_instant_runoff_winner(&self) -> Option<usize> {
+        let mut running = vec![true; self.candidates.len()];
+        loop {
+            let tally = self.count(&running);
+            let total: usize = tally.iter().sum();
+            if total == 0 {
+                return None;
+            }
+            let remaining: Vec<usize> = (0..running.len()).filter(|i| running[*i]).collect();
+            let leader = *remaining
+                .iter()
+                .max_by(|a, b| tally[**a].cmp(&tally[**b]).then(b.cmp(a)))?;
+            if tally[leader] * 2 > total || remaining.len() <= 2 {
+                return Some(leader);
+            }
+            let loser = *remaining
+                .iter()
+                .min_by(|a, b| tally[**a].cmp(&tally[**b]).then(b.cmp(a)))?;
+            running[loser] = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished_poll(method: Method, candidates: &[&str], ballots: &[&[usize]]) -> Poll {
+        let mut poll = Poll::new("map", Duration::from_secs(0), method, 5, false);
+        let candidates = candidates.iter().map(|name| (*name).to_owned()).collect();
+        assert!(poll.start_polling(candidates).is_ok());
+        for (voter, choices) in ballots.iter().enumerate() {
+            assert!(poll.vote(&voter.to_string(), choices).is_ok());
+        }
+        poll
+    }
+
+    fn winner(poll: &Poll) -> Option<String> {
+        match poll.get_result() {
+            Ok(winner) => winner,
+            Err(_) => panic!("the poll has no result"),
+        }
+    }
+
+    #[test]
+    fn nobody_voted() {
+        let poll = finished_poll(Method::InstantRunoff, &["a", "b"], &[]);
+        assert_eq!(winner(&poll), None);
+    }
+
+    #[test]
+    fn plurality_tie_goes_to_first_listed() {
+        let poll = finished_poll(Method::Plurality, &["a", "b", "c"], &[&[3], &[2]]);
+        assert_eq!(winner(&poll), Some("b".to_owned()));
+    }
+
+    #[test]
+    fn instant_runoff_majority_wins_at_once() {
+        let poll = finished_poll(
+            Method::InstantRunoff,
+            &["a", "b", "c"],
+            &[&[2], &[2, 1], &[3, 1]],
+        );
+        assert_eq!(winner(&poll), Some("b".to_owned()));
+    }
+
+    #[test]
+    fn instant_runoff_transfers_eliminated_ballots() {
+        let poll = finished_poll(
+            Method::InstantRunoff,
+            &["a", "b", "c"],
+            &[&[1], &[1], &[2], &[2], &[3, 2]],
+        );
+        assert_eq!(winner(&poll), Some("b".to_owned()));
+    }
+
+    #[test]
+    fn instant_runoff_eliminates_last_listed_on_tie() {
+        // Eliminating `a` instead would hand its ballot to `c`, which would win.
+        let poll = finished_poll(
+            Method::InstantRunoff,
+            &["a", "b", "c"],
+            &[&[1, 3], &[2], &[3]],
+        );
+        assert_eq!(winner(&poll), Some("a".to_owned()));
+    }
+
+    #[test]
+    fn instant_runoff_final_tie_goes_to_first_listed() {
+        let poll = finished_poll(Method::InstantRunoff, &["a", "b"], &[&[2], &[1]]);
+        assert_eq!(winner(&poll), Some("a".to_owned()));
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::cooldown::Cooldown;
+
+pub enum RtvError {
+    Disabled,
+    Duplicate,
+    Cooldown(f32),
+}
+
+/// Counts the players asking for a map vote until enough of them did.
+pub struct RockTheVote {
+    ratio: f32,
+    map_cooldown: Cooldown,
+    players: HashSet<String>,
+}
+
+impl RockTheVote {
+    pub fn new(ratio: f32, cooldown_duration: Duration) -> RockTheVote {
+        RockTheVote {
+            ratio,
+            map_cooldown: Cooldown::new(cooldown_duration),
+            players: HashSet::new(),
+        }
+    }
+    pub fn add(&mut self, id: &str) -> Result<(), RtvError> {
+        if self.ratio <= 0f32 {
+            return Err(RtvError::Disabled);
+        }
+        if self.map_cooldown.is_in_cooldown() {
+            return Err(RtvError::Cooldown(self.map_cooldown.get_remaining_time()));
+        }
+        if !self.players.insert(id.to_owned()) {
+            return Err(RtvError::Duplicate);
+        }
+        Ok(())
+    }
+    pub fn remove(&mut self, id: &str) {
+        self.players.remove(id);
+    }
+    pub fn clear(&mut self) {
+        self.players.clear();
+    }
+    /// Starts over after a map change, holding requests off for the cooldown.
+    pub fn reset(&mut self) {
+        self.clear();
+        self.map_cooldown.put_in_cooldown();
+    }
+    pub fn get_count(&self) -> usize {
+        self.players.len()
+    }
+    pub fn get_requirement(&self, voters: i8) -> usize {
+        ((voters.max(1) as f32 * self.ratio).ceil() as usize).max(1)
+    }
+    pub fn is_reached(&self, voters: i8) -> bool {
+        self.ratio > 0f32 && self.get_count() >= self.get_requirement(voters)
+    }
+}
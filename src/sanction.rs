@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use crate::cooldown::Cooldown;
+
+const ID_PLACEHOLDER: &str = "{id}";
+const IP_PLACEHOLDER: &str = "{ip}";
+
+pub struct Sanctions {
+    kick_command: String,
+    ban_command: String,
+    unban_command: String,
+    max_ban_duration: Duration,
+    bans: Vec<(String, Cooldown)>,
+}
+
+impl Sanctions {
+    pub fn new(
+        kick_command: &str,
+        ban_command: &str,
+        unban_command: &str,
+        max_ban_duration: Duration,
+    ) -> Sanctions {
+        Sanctions {
+            kick_command: kick_command.to_owned(),
+            ban_command: ban_command.to_owned(),
+            unban_command: unban_command.to_owned(),
+            max_ban_duration,
+            bans: Vec::new(),
+        }
+    }
+    pub fn get_max_ban_duration(&self) -> Duration {
+        self.max_ban_duration
+    }
+    pub fn get_kick_command(&self, id: &str) -> String {
+        self.kick_command.replace(ID_PLACEHOLDER, id)
+    }
+    /// Records a temporary ban and returns the command that applies it.
+    pub fn ban(&mut self, ip: &str, duration: Duration) -> String {
+        let mut cooldown = Cooldown::new(duration);
+        cooldown.put_in_cooldown();
+        self.bans.push((ip.to_owned(), cooldown));
+        self.ban_command.replace(IP_PLACEHOLDER, ip)
+    }
+    /// Returns when each ban that was not lifted yet ends.
+    pub fn get_bans(&self) -> HashMap<String, SystemTime> {
+        self.bans
+            .iter()
+            .map(|(ip, cooldown)| (ip.to_owned(), cooldown.get_expiry()))
+            .collect()
+    }
+    /// Puts back a ban saved before a restart. One that ran out in the meantime is
+    /// lifted by the next `get_expired`.
+    pub fn restore_ban(&mut self, ip: &str, expiry: SystemTime) {
+        let mut cooldown = Cooldown::new(self.max_ban_duration);
+        cooldown.set_expiry(expiry);
+        self.bans.push((ip.to_owned(), cooldown));
+    }
+    /// Forgets the bans that ran out and returns the commands that lift them.
+    pub fn get_expired(&mut self) -> Vec<String> {
+        let mut commands = Vec::new();
+        let unban_command = &self.unban_command;
+        self.bans.retain(|(ip, cooldown)| {
+            if cooldown.is_in_cooldown() {
+                return true;
+            }
+            commands.push(unban_command.replace(IP_PLACEHOLDER, ip));
+            false
+        });
+        commands
+    }
+}
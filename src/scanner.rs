@@ -1,171 +1,365 @@
-use lazy_static::lazy_static;
-
-use regex::Regex;
-use std::fs::File;
+use std::fs::{self, File, Metadata};
 use std::io::prelude::*;
-use std::io::BufReader;
+use std::io::{self, BufReader, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, sleep};
+use std::time::Duration;
+
+use crate::event::Event;
+use crate::watcher::Watcher;
+
+const STDIN: &str = "-";
 
-lazy_static! {
-    static ref REGEX_INIT: Regex = Regex::new(r#"^ *(?P<minute>[0-9]+):(?P<second>[0-9]{2}) *InitGame:"#).unwrap();
-    static ref REGEX_SHUTDOWN: Regex = Regex::new(r#"^ *(?P<minute>[0-9]+):(?P<second>[0-9]{2}) *ShutdownGame:"#).unwrap();
-    static ref REGEX_CONNECT: Regex =
-        Regex::new(r#"^ *(?P<minute>[0-9]+):(?P<second>[0-9]{2}) *ClientConnect: (?P<id>[0-9]{1,2})"#).unwrap();
-    static ref REGEX_DISCONNECT: Regex =
-        Regex::new(r#"^ *(?P<minute>[0-9]+):(?P<second>[0-9]{2}) *ClientDisconnect: (?P<id>[0-9]{1,2})"#).unwrap();
-    static ref REGEX_CHAT: Regex = Regex::new(
-        r#"^ *(?P<minute>[0-9]+):(?P<second>[0-9]{2}) *(?P<id>[0-9]{1,2}): say: (?P<username>.*): "(?P<message>.*)""#
-    )
-    .unwrap();
+/// Where the log lines come from.
+trait Source: Send {
+    /// Appends the next line, or what there is of it yet; 0 when nothing is left.
+    fn read_line(&mut self, buffer: &mut Vec<u8>) -> io::Result<usize>;
+    /// Starts over on a new log once the current one was read to its end.
+    fn reopen(&mut self) -> bool;
+    fn wait(&mut self, interval: Duration);
 }
 
+/// Reads the game log from a file, standard input (`-`) or a named pipe.
 pub struct Scanner {
-    buffer: BufReader<File>,
+    source: Box<dyn Source>,
+    pending: Vec<u8>,
+    read_errors: usize,
 }
 
-pub enum Event {
-    Init(String, String),
-    Shutdown(String, String),
-    Connect(String, String, String),
-    Disconnect(String, String, String),
-    Chat(String, String, String, String, String),
+impl Scanner {
+    pub fn new(path: &str, watch: bool) -> Scanner {
+        let source: Box<dyn Source> = if path == STDIN {
+            Box::new(Pipe::stdin())
+        } else if is_fifo(path) {
+            Box::new(Pipe::fifo(path))
+        } else {
+            Box::new(LogFile::new(path, watch))
+        };
+        Self {
+            source,
+            pending: Vec::new(),
+            read_errors: 0,
+        }
+    }
+
+    /// Waits for the log to change, or for the whole interval when it is polled.
+    pub fn wait(&mut self, interval: Duration) {
+        self.source.wait(interval);
+    }
+
+    pub fn events(&mut self) -> Events<'_> {
+        Events {
+            scanner: self,
+            failed: false,
+        }
+    }
+
+    /// Counts the read errors since the scanner was created.
+    pub fn get_read_errors(&self) -> usize {
+        self.read_errors
+    }
 }
 
-impl Scanner {
-    pub fn new(path: &str) -> Scanner {
-        let file = File::open(path).expect("cannot open log file");
+/// Follows the game log across rotations. The path may also be a glob such as
+/// `games_*.log`, in which case the most recently modified match is followed.
+struct LogFile {
+    pattern: Option<String>,
+    path: PathBuf,
+    buffer: BufReader<File>,
+    file_id: u64,
+    position: u64,
+    watcher: Option<Watcher>,
+}
+
+impl LogFile {
+    fn new(path: &str, watch: bool) -> LogFile {
+        let pattern = match path.contains(&['*', '?', '['][..]) {
+            true => Some(path.to_owned()),
+            false => None,
+        };
+        let path = match &pattern {
+            Some(pattern) => get_newest_match(pattern).expect("no log file matches the pattern"),
+            None => PathBuf::from(path),
+        };
+        let file = File::open(&path).expect("cannot open log file");
+        let file_id = get_file_id(&file.metadata().expect("cannot read log file metadata"));
         let mut buffer: BufReader<File> = BufReader::new(file);
-        buffer
-            .seek(std::io::SeekFrom::End(0))
+        let position = buffer
+            .seek(SeekFrom::End(0))
             .expect("buffer could not seek to end");
-        Self { buffer: buffer }
+        let watcher = match watch {
+            true => {
+                let directory = match path.parent() {
+                    Some(directory) if directory != Path::new("") => directory,
+                    _ => Path::new("."),
+                };
+                match Watcher::new(directory) {
+                    Ok(watcher) => Some(watcher),
+                    Err(error) => {
+                        eprintln!("could not watch the log, polling instead: {}", error);
+                        None
+                    }
+                }
+            }
+            false => None,
+        };
+        LogFile {
+            pattern,
+            path,
+            buffer,
+            file_id,
+            position,
+            watcher,
+        }
     }
 
-    pub fn events(&mut self) -> Events {
-        Events {
-            buffer: &mut self.buffer,
-            string_buffer: String::new(),
+    fn open(&mut self, path: PathBuf) -> bool {
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(_) => return false,
+        };
+        let file_id = match file.metadata() {
+            Ok(metadata) => get_file_id(&metadata),
+            Err(_) => return false,
+        };
+        self.buffer = BufReader::new(file);
+        self.path = path;
+        self.file_id = file_id;
+        self.position = 0;
+        true
+    }
+}
+
+impl Source for LogFile {
+    fn read_line(&mut self, buffer: &mut Vec<u8>) -> io::Result<usize> {
+        let length = buffer.len();
+        let result = self.buffer.read_until(b'\n', buffer);
+        self.position += (buffer.len() - length) as u64;
+        result
+    }
+
+    /// Switches to the start of the new file once the log was rotated, recreated or
+    /// truncated.
+    fn reopen(&mut self) -> bool {
+        if let Some(pattern) = &self.pattern {
+            if let Some(path) = get_newest_match(pattern) {
+                if path != self.path {
+                    return self.open(path);
+                }
+            }
+        }
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(_) => return false,
+        };
+        if get_file_id(&metadata) != self.file_id {
+            return self.open(self.path.clone());
+        }
+        if metadata.len() < self.position {
+            if let Ok(position) = self.buffer.seek(SeekFrom::Start(0)) {
+                self.position = position;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn wait(&mut self, interval: Duration) {
+        match &mut self.watcher {
+            Some(watcher) => watcher.wait(interval),
+            None => sleep(interval),
         }
     }
 }
 
-pub struct Events<'a> {
-    buffer: &'a mut BufReader<File>,
-    string_buffer: String,
+/// Reads a stream that can't seek on its own thread, handing over whole lines.
+struct Pipe {
+    receiver: Receiver<io::Result<Vec<u8>>>,
+    received: Option<io::Result<Vec<u8>>>,
+    closed: bool,
 }
 
-impl<'a> Iterator for Events<'a> {
-    type Item = Event;
-    fn next(&mut self) -> Option<Event> {
-        self.string_buffer.clear();
-        self.buffer
-            .read_line(&mut self.string_buffer)
-            .expect("error reading log file");
-        let line = &self.string_buffer;
-        match REGEX_INIT.captures(line) {
-            Some(captures) => {
-                let minute = captures
-                    .name("minute")
-                    .expect("could not get init 'minute'")
-                    .as_str()
-                    .to_owned();
-                let second = captures
-                    .name("second")
-                    .expect("could not get init 'second'")
-                    .as_str()
-                    .to_owned();
-                return Some(Event::Init(minute, second));
+impl Pipe {
+    fn stdin() -> Pipe {
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let stdin = io::stdin();
+            send_lines(stdin.lock(), &sender);
+        });
+        Pipe::new(receiver)
+    }
+
+    /// Opens the pipe again whenever the writer closed it, like when the server restarts.
+    fn fifo(path: &str) -> Pipe {
+        let (sender, receiver) = channel();
+        let path = path.to_owned();
+        thread::spawn(move || loop {
+            match File::open(&path) {
+                Ok(file) => {
+                    if !send_lines(BufReader::new(file), &sender) {
+                        return;
+                    }
+                }
+                Err(error) => {
+                    sender.send(Err(error)).ok();
+                    return;
+                }
             }
-            None => {}
+        });
+        Pipe::new(receiver)
+    }
+
+    fn new(receiver: Receiver<io::Result<Vec<u8>>>) -> Pipe {
+        Pipe {
+            receiver,
+            received: None,
+            closed: false,
         }
-        match REGEX_SHUTDOWN.captures(line) {
-            Some(captures) => {
-                let minute = captures
-                    .name("minute")
-                    .expect("could not get shutdown 'minute'")
-                    .as_str()
-                    .to_owned();
-                let second = captures
-                    .name("second")
-                    .expect("could not get shutdown 'second'")
-                    .as_str()
-                    .to_owned();
-                return Some(Event::Shutdown(minute, second));
-            }
-            None => {}
+    }
+}
+
+impl Source for Pipe {
+    fn read_line(&mut self, buffer: &mut Vec<u8>) -> io::Result<usize> {
+        let line = match self.received.take() {
+            Some(line) => line,
+            None => match self.receiver.try_recv() {
+                Ok(line) => line,
+                Err(_) => return Ok(0),
+            },
+        }?;
+        buffer.extend_from_slice(&line);
+        Ok(line.len())
+    }
+
+    fn reopen(&mut self) -> bool {
+        false
+    }
+
+    fn wait(&mut self, interval: Duration) {
+        if self.received.is_some() {
+            return;
         }
-        match REGEX_CONNECT.captures(line) {
-            Some(captures) => {
-                let minute = captures
-                    .name("minute")
-                    .expect("could not get connect 'minute'")
-                    .as_str()
-                    .to_owned();
-                let second = captures
-                    .name("second")
-                    .expect("could not get connect 'second'")
-                    .as_str()
-                    .to_owned();
-                let id = captures
-                    .name("id")
-                    .expect("could not get connect 'id'")
-                    .as_str()
-                    .to_owned();
-                return Some(Event::Connect(minute, second, id));
+        match self.receiver.recv_timeout(interval) {
+            Ok(line) => self.received = Some(line),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                if !self.closed {
+                    eprintln!("the log input was closed");
+                    self.closed = true;
+                }
+                sleep(interval);
             }
-            None => {}
         }
-        match REGEX_DISCONNECT.captures(line) {
-            Some(captures) => {
-                let minute = captures
-                    .name("minute")
-                    .expect("could not get disconnect 'minute'")
-                    .as_str()
-                    .to_owned();
-                let second = captures
-                    .name("second")
-                    .expect("could not get disconnect 'second'")
-                    .as_str()
-                    .to_owned();
-                let id = captures
-                    .name("id")
-                    .expect("could not get disconnect 'id'")
-                    .as_str()
-                    .to_owned();
-                return Some(Event::Disconnect(minute, second, id));
+    }
+}
+
+/// Sends every line until the end of the stream, returning `false` once the
+/// scanner is gone or the stream failed.
+fn send_lines<R: BufRead>(mut reader: R, sender: &Sender<io::Result<Vec<u8>>>) -> bool {
+    loop {
+        let mut line = Vec::new();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return true,
+            Ok(_) => {
+                // The writer may have stopped in the middle of a line.
+                if !line.ends_with(b"\n") {
+                    line.push(b'\n');
+                }
+                if sender.send(Ok(line)).is_err() {
+                    return false;
+                }
+            }
+            Err(error) => {
+                sender.send(Err(error)).ok();
+                return false;
             }
-            None => {}
         }
-        match REGEX_CHAT.captures(line) {
-            Some(captures) => {
-                let minute = captures
-                    .name("minute")
-                    .expect("could not get chat 'minute'")
-                    .as_str()
-                    .to_owned();
-                let second = captures
-                    .name("second")
-                    .expect("could not get chat 'second'")
-                    .as_str()
-                    .to_owned();
-                let id = captures
-                    .name("id")
-                    .expect("could not get chat 'id'")
-                    .as_str()
-                    .to_owned();
-                let username = captures
-                    .name("username")
-                    .expect("could not get chat 'username'")
-                    .as_str()
-                    .to_owned();
-                let message = captures
-                    .name("message")
-                    .expect("could not get chat 'message'")
-                    .as_str()
-                    .to_owned();
-                return Some(Event::Chat(minute, second, id, username, message));
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &str) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    match fs::metadata(path) {
+        Ok(metadata) => metadata.file_type().is_fifo(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &str) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn get_file_id(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+/// Without inodes a recreated log is only noticed when it is shorter.
+#[cfg(not(unix))]
+fn get_file_id(_metadata: &Metadata) -> u64 {
+    0
+}
+
+fn get_newest_match(pattern: &str) -> Option<PathBuf> {
+    glob::glob(pattern)
+        .ok()?
+        .filter_map(|path| path.ok())
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).ok()?.modified().ok()?;
+            Some((modified, path))
+        })
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// Yields the events logged since the last call, skipping the lines it does not
+/// know. A read error is yielded once and ends the iteration, so the next call
+/// retries from where it stopped.
+pub struct Events<'a> {
+    scanner: &'a mut Scanner,
+    failed: bool,
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = io::Result<Event>;
+    fn next(&mut self) -> Option<io::Result<Event>> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            match self.scanner.source.read_line(&mut self.scanner.pending) {
+                Ok(0) => {
+                    if !self.scanner.source.reopen() {
+                        // The rest of an unfinished line may still be on its way.
+                        return None;
+                    }
+                    // What is left of the old file will not be finished anymore.
+                    if self.scanner.pending.is_empty() {
+                        continue;
+                    }
+                }
+                Ok(_) => {
+                    if !self.scanner.pending.ends_with(b"\n") {
+                        continue;
+                    }
+                }
+                Err(error) => {
+                    self.failed = true;
+                    self.scanner.pending.clear();
+                    self.scanner.read_errors += 1;
+                    return Some(Err(error));
+                }
+            }
+            // Player names are not always valid UTF-8.
+            let event = String::from_utf8_lossy(&self.scanner.pending).parse::<Event>();
+            self.scanner.pending.clear();
+            if let Ok(event) = event {
+                return Some(Ok(event));
             }
-            None => {}
         }
-        None
     }
 }
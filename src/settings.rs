@@ -0,0 +1,279 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Error, ErrorKind, Result};
+
+use clap::ArgMatches;
+use config::{Config, ConfigError, Environment, File, Source};
+use serde::Deserialize;
+
+use crate::messages::Messages;
+use crate::nomination::Nomination;
+use crate::util::get_maplist;
+
+const ENVIRONMENT_PREFIX: &str = "DEMOCRACY";
+const DEFAULT_CONFIG: &str = "democracy";
+
+const OVERRIDES: [&str; 12] = [
+    "maps",
+    "rcon",
+    "host_ip",
+    "host_port",
+    "bind",
+    "client_port",
+    "log",
+    "interval",
+    "timeout",
+    "voting_duration",
+    "player_cooldown",
+    "target",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct Settings {
+    pub maps: String,
+    pub rcon: String,
+    pub host_ip: String,
+    pub host_port: u16,
+    pub bind: Option<String>,
+    /// Deprecated, the same as `bind = "0.0.0.0:<port>"`.
+    pub client_port: Option<u16>,
+    pub log: String,
+    pub interval: u64,
+    pub watch: bool,
+    pub timeout: u64,
+    pub rcon_rate: f32,
+    pub rcon_retries: usize,
+    pub voting_duration: u64,
+    pub player_cooldown: u64,
+    pub target: f32,
+    pub kick_command: String,
+    pub ban_command: String,
+    pub unban_command: String,
+    pub max_ban_duration: u64,
+    pub spectators_vote: bool,
+    pub store: String,
+    pub history_limit: usize,
+    pub map_rotation_cooldown: usize,
+    pub poll_nomination: String,
+    pub poll_duration: u64,
+    pub poll_size: usize,
+    pub poll_method: String,
+    pub poll_on_shutdown: bool,
+    pub rtv_ratio: f32,
+    pub rtv_cooldown: u64,
+    #[serde(default)]
+    pub nominations: HashMap<String, NominationSettings>,
+    pub messages_file: String,
+    #[serde(default)]
+    pub messages: HashMap<String, String>,
+    pub name: String,
+    pub share_bans: bool,
+    /// Each entry of the `servers` list, over the settings above.
+    #[serde(skip)]
+    pub servers: Vec<Settings>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NominationSettings {
+    pub name: Option<String>,
+    pub keyword: Option<String>,
+    #[serde(default)]
+    pub values: Vec<String>,
+    pub file: Option<String>,
+    pub command: String,
+    #[serde(default)]
+    pub rotation: bool,
+}
+
+/// Sets the `DEMOCRACY_*` environment variables and the command line over the
+/// config, which is done again over each server so that they win there too.
+fn apply_overrides(
+    config: &mut Config,
+    matches: &ArgMatches,
+) -> std::result::Result<(), ConfigError> {
+    for (key, value) in Environment::with_prefix(ENVIRONMENT_PREFIX).collect()? {
+        config.set(&key, value)?;
+    }
+    for key in OVERRIDES.iter() {
+        if let Some(value) = matches.value_of(key) {
+            config.set(key, value)?;
+        }
+    }
+    Ok(())
+}
+
+impl Settings {
+    /// Layers the settings from lowest to highest priority: built-in defaults,
+    /// the config file, `DEMOCRACY_*` environment variables and the command line.
+    pub fn new(matches: &ArgMatches) -> std::result::Result<Settings, ConfigError> {
+        let mut config = Config::new();
+        config.set_default("maps", "./maps.txt")?;
+        config.set_default("rcon", "password")?;
+        config.set_default("host_ip", "127.0.0.1")?;
+        config.set_default("host_port", 29070)?;
+        config.set_default("log", "./games.log")?;
+        config.set_default("interval", 1)?;
+        config.set_default("watch", true)?;
+        config.set_default("timeout", 100)?;
+        config.set_default("rcon_rate", 5)?;
+        config.set_default("rcon_retries", 2)?;
+        config.set_default("voting_duration", 30)?;
+        config.set_default("player_cooldown", 30)?;
+        config.set_default("target", 0.6)?;
+        config.set_default("kick_command", "clientkick {id}")?;
+        config.set_default("ban_command", "addip {ip}")?;
+        config.set_default("unban_command", "removeip {ip}")?;
+        config.set_default("max_ban_duration", 60)?;
+        config.set_default("spectators_vote", true)?;
+        config.set_default("store", "./democracy_store.json")?;
+        config.set_default("history_limit", 1000)?;
+        config.set_default("map_rotation_cooldown", 0)?;
+        config.set_default("poll_nomination", "map")?;
+        config.set_default("poll_duration", 30)?;
+        config.set_default("poll_size", 5)?;
+        config.set_default("poll_method", "plurality")?;
+        config.set_default("poll_on_shutdown", false)?;
+        config.set_default("rtv_ratio", 0.5)?;
+        config.set_default("rtv_cooldown", 180)?;
+        config.set_default("messages_file", "")?;
+        config.set_default("name", "")?;
+        config.set_default("share_bans", false)?;
+        match matches.value_of("config") {
+            Some(path) => config.merge(File::with_name(path))?,
+            None => config.merge(File::with_name(DEFAULT_CONFIG).required(false))?,
+        };
+        apply_overrides(&mut config, matches)?;
+        let servers = match config.get_array("servers") {
+            Ok(servers) => servers,
+            Err(ConfigError::NotFound(_)) => Vec::new(),
+            Err(error) => return Err(error),
+        };
+        let mut settings: Settings = config.clone().try_into()?;
+        for (index, server) in servers.into_iter().enumerate() {
+            let mut server_config = config.clone();
+            for (key, value) in server.into_table()? {
+                server_config.set(&key, value)?;
+            }
+            apply_overrides(&mut server_config, matches)?;
+            let mut server: Settings = server_config.try_into()?;
+            if server.name.is_empty() {
+                server.name = format!("server{}", index + 1);
+            }
+            settings.servers.push(server);
+        }
+        Ok(settings)
+    }
+
+    /// Layers the chat messages: built-in, the `messages_file` catalog, then the
+    /// `messages` table.
+    pub fn get_messages(&self) -> Result<Messages> {
+        let mut templates: HashMap<String, String> = HashMap::new();
+        if !self.messages_file.is_empty() {
+            let mut catalog = Config::new();
+            catalog
+                .merge(File::with_name(&self.messages_file))
+                .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+            templates = catalog
+                .try_into()
+                .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+        }
+        templates.extend(self.messages.clone());
+        Messages::new(&templates)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error.to_string()))
+    }
+
+    /// The local address of the rcon socket, from `bind` or the older `client_port`.
+    pub fn get_bind(&self) -> Option<String> {
+        match (&self.bind, self.client_port) {
+            (Some(bind), _) => Some(bind.to_owned()),
+            (None, Some(port)) => Some(format!("0.0.0.0:{}", port)),
+            (None, None) => None,
+        }
+    }
+
+    /// The servers to run, this one alone when no `servers` are listed.
+    pub fn get_servers(&self) -> Vec<&Settings> {
+        match self.servers.is_empty() {
+            true => vec![self],
+            false => self.servers.iter().collect(),
+        }
+    }
+
+    /// Falls back to the `map` and `mode` categories when none are configured.
+    /// The returned table is keyed by the chat keyword of each category.
+    pub fn get_nominations(&self) -> Result<HashMap<String, Nomination>> {
+        let mut nominations = HashMap::new();
+        if self.nominations.is_empty() {
+            nominations.insert(
+                "map".to_owned(),
+                Nomination::new("Map", "map", get_maplist(&self.maps)?, "map {value}", true),
+            );
+            nominations.insert(
+                "mode".to_owned(),
+                Nomination::new(
+                    "Mode",
+                    "mode",
+                    (0..5).map(|mode: u8| mode.to_string()).collect(),
+                    "mbmode {value}",
+                    false,
+                ),
+            );
+            return Ok(nominations);
+        }
+        for (key, nomination) in &self.nominations {
+            let mut values: HashSet<String> = nomination.values.iter().cloned().collect();
+            if let Some(file) = &nomination.file {
+                values.extend(get_maplist(file)?);
+            }
+            let name = nomination.name.as_deref().unwrap_or(key);
+            let keyword = nomination.keyword.as_deref().unwrap_or(key);
+            nominations.insert(
+                keyword.to_owned(),
+                Nomination::new(
+                    name,
+                    keyword,
+                    values,
+                    &nomination.command,
+                    nomination.rotation,
+                ),
+            );
+        }
+        Ok(nominations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use clap::{App, Arg};
+
+    #[test]
+    fn command_line_wins_over_server_settings() {
+        let path = std::env::temp_dir().join("democracy_settings_test.toml");
+        fs::write(
+            &path,
+            "target = 0.5\n\
+             [[servers]]\n\
+             name = \"duel\"\n\
+             target = 0.9\n\
+             rcon = \"file\"\n",
+        )
+        .expect("could not write settings");
+        let path = path.to_str().expect("could not get settings path");
+        let matches = App::new("test")
+            .arg(Arg::with_name("config").long("config").takes_value(true))
+            .arg(Arg::with_name("target").long("target").takes_value(true))
+            .arg(Arg::with_name("rcon").long("rcon").takes_value(true))
+            .get_matches_from(vec![
+                "test", "--config", path, "--target", "0.1", "--rcon", "secret",
+            ]);
+        let settings = Settings::new(&matches).expect("could not read settings");
+        fs::remove_file(path).ok();
+        let server = settings.get_servers()[0];
+        assert_eq!(server.name, "duel");
+        assert_eq!(server.target, 0.1);
+        assert_eq!(server.rcon, "secret");
+    }
+}
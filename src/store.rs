@@ -0,0 +1,209 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+
+#[derive(Serialize, Deserialize)]
+pub enum Outcome {
+    Granted,
+    Denied,
+    Deadlock,
+    Dismissed,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Record {
+    pub proposer: String,
+    pub r#type: String,
+    pub value: String,
+    pub yays: i8,
+    pub nays: i8,
+    pub voters: HashMap<String, bool>,
+    pub outcome: Outcome,
+    pub started: u64,
+    pub finished: u64,
+    /// The server the vote was held on, when several servers share the store.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub server: String,
+}
+
+/// What is kept for each server, by player identity or IP address, and the maps it played.
+#[derive(Default, Serialize, Deserialize)]
+struct ServerData {
+    #[serde(default)]
+    cooldowns: HashMap<String, u64>,
+    #[serde(default)]
+    bans: HashMap<String, u64>,
+    /// The last maps that were loaded, oldest first.
+    #[serde(default)]
+    recent_maps: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Data {
+    history: Vec<Record>,
+    #[serde(flatten)]
+    server: ServerData,
+    /// The data of the named servers, which is kept apart.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    servers: HashMap<String, ServerData>,
+}
+
+impl Data {
+    fn get_server_mut(&mut self, server: &str) -> &mut ServerData {
+        match server.is_empty() {
+            true => &mut self.server,
+            false => self.servers.entry(server.to_owned()).or_default(),
+        }
+    }
+}
+
+/// Keeps the vote history, the player cooldowns, the running bans and the recently
+/// played maps in a JSON file so that they survive a restart.
+pub struct Store {
+    path: Option<PathBuf>,
+    history_limit: usize,
+    data: Data,
+}
+
+impl Store {
+    pub fn open(path: &str, history_limit: usize) -> Result<Store> {
+        let path = PathBuf::from(path);
+        let data = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|error| Error::new(ErrorKind::InvalidData, error))?,
+            Err(ref error) if error.kind() == ErrorKind::NotFound => Data::default(),
+            Err(error) => return Err(error),
+        };
+        Ok(Store {
+            path: Some(path),
+            history_limit,
+            data,
+        })
+    }
+    /// A store that starts empty and is never written, for replays.
+    pub fn in_memory(history_limit: usize) -> Store {
+        Store {
+            path: None,
+            history_limit,
+            data: Data::default(),
+        }
+    }
+    /// Writes to a temporary file first so a crash can't leave a truncated store behind.
+    pub fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let bytes = serde_json::to_vec_pretty(&self.data)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+        let mut temporary = path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, bytes)?;
+        fs::rename(&temporary, path)
+    }
+    pub fn record(&mut self, record: Record) {
+        self.data.history.push(record);
+        if self.data.history.len() > self.history_limit {
+            let excess = self.data.history.len() - self.history_limit;
+            self.data.history.drain(..excess);
+        }
+    }
+    pub fn get_cooldowns(&mut self, server: &str) -> HashMap<String, SystemTime> {
+        from_timestamps(&self.data.get_server_mut(server).cooldowns)
+            .into_iter()
+            .filter(|(_, expiry)| *expiry > clock::now())
+            .collect()
+    }
+    pub fn set_cooldowns(&mut self, server: &str, cooldowns: HashMap<String, SystemTime>) {
+        self.data.get_server_mut(server).cooldowns = to_timestamps(cooldowns);
+    }
+    /// Also returns the bans that ran out while nothing was running, which still
+    /// need to be lifted.
+    pub fn get_bans(&mut self, server: &str) -> HashMap<String, SystemTime> {
+        from_timestamps(&self.data.get_server_mut(server).bans)
+    }
+    pub fn set_bans(&mut self, server: &str, bans: HashMap<String, SystemTime>) {
+        self.data.get_server_mut(server).bans = to_timestamps(bans);
+    }
+    pub fn get_recent_maps(&mut self, server: &str) -> Vec<String> {
+        self.data.get_server_mut(server).recent_maps.clone()
+    }
+    pub fn set_recent_maps(&mut self, server: &str, maps: Vec<String>) {
+        self.data.get_server_mut(server).recent_maps = maps;
+    }
+}
+
+/// The part of a store that belongs to one server. Servers configured with the
+/// same store file share its history.
+pub struct ServerStore {
+    store: Rc<RefCell<Store>>,
+    server: String,
+}
+
+impl ServerStore {
+    pub fn new(store: Rc<RefCell<Store>>, server: &str) -> ServerStore {
+        ServerStore {
+            store,
+            server: server.to_owned(),
+        }
+    }
+    pub fn save(&self) -> Result<()> {
+        self.store.borrow().save()
+    }
+    pub fn record(&mut self, mut record: Record) {
+        record.server = self.server.to_owned();
+        self.store.borrow_mut().record(record);
+    }
+    pub fn get_cooldowns(&mut self) -> HashMap<String, SystemTime> {
+        self.store.borrow_mut().get_cooldowns(&self.server)
+    }
+    pub fn set_cooldowns(&mut self, cooldowns: HashMap<String, SystemTime>) {
+        self.store
+            .borrow_mut()
+            .set_cooldowns(&self.server, cooldowns);
+    }
+    pub fn get_bans(&mut self) -> HashMap<String, SystemTime> {
+        self.store.borrow_mut().get_bans(&self.server)
+    }
+    pub fn set_bans(&mut self, bans: HashMap<String, SystemTime>) {
+        self.store.borrow_mut().set_bans(&self.server, bans);
+    }
+    pub fn get_recent_maps(&mut self) -> Vec<String> {
+        self.store.borrow_mut().get_recent_maps(&self.server)
+    }
+    pub fn set_recent_maps(&mut self, maps: Vec<String>) {
+        self.store.borrow_mut().set_recent_maps(&self.server, maps);
+    }
+}
+
+pub fn to_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn from_timestamp(timestamp: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp)
+}
+
+fn to_timestamps(times: HashMap<String, SystemTime>) -> HashMap<String, u64> {
+    times
+        .into_iter()
+        .map(|(key, time)| (key, to_timestamp(time)))
+        .collect()
+}
+
+fn from_timestamps(timestamps: &HashMap<String, u64>) -> HashMap<String, SystemTime> {
+    timestamps
+        .iter()
+        .map(|(key, timestamp)| (key.to_owned(), from_timestamp(*timestamp)))
+        .collect()
+}
@@ -1,17 +1,16 @@
 use lazy_static::lazy_static;
 
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
 use std::io::prelude::*;
 use std::io::{BufReader, Result};
-use std::collections::HashSet;
 
 use regex::Regex;
 
-use crate::console::Console;
-
 lazy_static! {
-    static ref REGEX_INFO: Regex =
-        Regex::new(r#"\\g_humanplayers\\(?P<player_count>[0-9]{1,2})\\"#).unwrap();
+    static ref REGEX_COLOR: Regex = Regex::new(r#"\^[0-9]"#).unwrap();
 }
 
 pub fn get_maplist(path: &str) -> Result<HashSet<String>> {
@@ -24,21 +23,43 @@ pub fn get_maplist(path: &str) -> Result<HashSet<String>> {
     Ok(output)
 }
 
-pub fn get_server_info(console: &mut Console) -> Option<i8> {
-    for message in console.send(b"getinfo").expect("could not get server info") {
-        match REGEX_INFO.captures(&message) {
-            Some(captures) => {
-                return Some(
-                    captures
-                        .name("player_count")
-                        .expect("could not get player count")
-                        .as_str()
-                        .parse()
-                        .expect("could not parse player count"),
-                );
-            }
-            None => {}
+/// Parses a `key\value\key\value` info string, with or without the leading backslash.
+pub fn get_userinfo(userinfo: &str) -> HashMap<String, String> {
+    let mut output = HashMap::new();
+    let mut fields = userinfo.trim_start_matches('\\').split('\\');
+    while let (Some(key), Some(value)) = (fields.next(), fields.next()) {
+        output.insert(key.to_owned(), value.to_owned());
+    }
+    output
+}
+
+pub fn strip_colors(text: &str) -> String {
+    REGEX_COLOR.replace_all(text, "").into_owned()
+}
+
+/// Shuffles in place, seeded by the randomly keyed hasher of the standard library.
+pub fn shuffle<T>(items: &mut [T]) {
+    let mut state = RandomState::new().build_hasher().finish() | 1;
+    for index in (1..items.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        items.swap(index, (state % (index as u64 + 1)) as usize);
+    }
+}
+
+/// Counts the single character insertions, deletions and substitutions turning `a` into `b`.
+pub fn get_edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + if a_char == *b_char { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
         }
     }
-    None
+    row[b.len()]
 }
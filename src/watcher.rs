@@ -0,0 +1,70 @@
+use std::io;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+use inotify::{Inotify, WatchMask};
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
+
+/// Wakes up as soon as something in the log directory changes, so new lines are
+/// read right away instead of on the next polling interval.
+#[cfg(target_os = "linux")]
+pub struct Watcher {
+    inotify: Inotify,
+    buffer: [u8; 4096],
+}
+
+#[cfg(target_os = "linux")]
+impl Watcher {
+    /// Watches the whole directory to also notice the log being recreated.
+    pub fn new(directory: &Path) -> io::Result<Watcher> {
+        let mut inotify = Inotify::init()?;
+        inotify.add_watch(
+            directory,
+            WatchMask::MODIFY | WatchMask::CREATE | WatchMask::MOVED_TO | WatchMask::DELETE,
+        )?;
+        Ok(Watcher {
+            inotify,
+            buffer: [0; 4096],
+        })
+    }
+    /// Blocks until a change was notified or the timeout passed.
+    pub fn wait(&mut self, timeout: Duration) {
+        let mut fd = libc::pollfd {
+            fd: self.inotify.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let milliseconds = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+        let ready = unsafe { libc::poll(&mut fd, 1, milliseconds) };
+        if ready < 0 {
+            sleep(timeout);
+            return;
+        }
+        // The descriptor is non-blocking, so this stops once the queue is empty.
+        while let Ok(mut events) = self.inotify.read_events(&mut self.buffer) {
+            if events.next().is_none() {
+                break;
+            }
+        }
+    }
+}
+
+/// Only Linux has file notifications here, other platforms keep polling.
+#[cfg(not(target_os = "linux"))]
+pub struct Watcher;
+
+#[cfg(not(target_os = "linux"))]
+impl Watcher {
+    pub fn new(_directory: &Path) -> io::Result<Watcher> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "file notifications are not supported on this platform",
+        ))
+    }
+    pub fn wait(&mut self, timeout: Duration) {
+        sleep(timeout);
+    }
+}